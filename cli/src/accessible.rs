@@ -0,0 +1,511 @@
+use std::{
+    io::{self, BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use sweeprs::{
+    BoardResult, BoardState, CellKind, CellState, Error, Pos, SweeperBoard, SweeperCell,
+};
+
+use crate::{
+    config::{Difficulty, Level},
+    game::explain,
+    scores,
+};
+
+const HELP: &str = "\
+Cells are named by their column letter and row number, a1 is the top left.
+open c5, or o c5: open a cell, or the cells around an open number
+flag c5, or f c5: flag or unflag a cell
+read around c5, or read c5: a cell and its neighbours
+read row 3: every cell of a row
+read column c: every cell of a column
+status: mines left, closed cells and time
+hint: a cell to open next, and why
+new, or new easy, new medium, new hard: start another game
+quit: leave the game";
+
+/// What a line typed by the player asks for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    Open(Pos),
+    Flag(Pos),
+    Around(Pos),
+    Row(usize),
+    Column(usize),
+    Status,
+    Hint,
+    /// Another game, on the same board unless a difficulty is given.
+    New(Option<Difficulty>),
+    Help,
+    Quit,
+}
+
+/// Game played one command per line, answered in plain sentences, for
+/// screen readers and other terminals that cannot follow a drawn board.
+pub struct AccessibleGame<B: SweeperBoard> {
+    sweeper: B,
+    level: Level,
+    started: Option<Instant>,
+    /// Time of the game once it is finished.
+    time: Option<Duration>,
+    hints: usize,
+    /// Name finished games are recorded under, games are not recorded
+    /// without one.
+    player: Option<String>,
+}
+
+impl<B: SweeperBoard> AccessibleGame<B> {
+    pub fn new(level: Level) -> Result<Self, Error> {
+        let (height, width, mine_count) = level.dimension();
+        Ok(Self::with_board(level, B::new(height, width, mine_count)?))
+    }
+
+    /// Same as `new`, with the mines placed from `seed`.
+    #[cfg(test)]
+    pub fn with_seed(level: Level, seed: u64) -> Result<Self, Error> {
+        let (height, width, mine_count) = level.dimension();
+        let sweeper = B::with_seed(height, width, mine_count, seed)?;
+        Ok(Self::with_board(level, sweeper))
+    }
+
+    fn with_board(level: Level, sweeper: B) -> Self {
+        Self {
+            sweeper,
+            level,
+            started: None,
+            time: None,
+            hints: 0,
+            player: None,
+        }
+    }
+
+    /// Save every finished game in the high scores under `name`.
+    pub fn record_as(&mut self, name: String) {
+        self.player = Some(name);
+    }
+
+    /// Answer every line of `input` until it ends or the player quits.
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "{}", self.intro())?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match parse(&line) {
+                Ok(Command::Quit) => break,
+                Ok(command) => self.execute(command).unwrap_or_else(|e| e),
+                Err(e) => e,
+            };
+            writeln!(output, "{}", reply)?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    fn intro(&self) -> String {
+        let (height, width, mine_count) = self.level.dimension();
+        format!(
+            "New {} game, {} columns from a to {}, {} rows from 1 to {}, {} mines. \
+             Type help for the commands.",
+            self.level.name(),
+            width,
+            column_name(width - 1),
+            height,
+            height,
+            mine_count
+        )
+    }
+
+    fn execute(&mut self, command: Command) -> Result<String, String> {
+        match command {
+            Command::Open(pos) => self.open(self.check(pos)?),
+            Command::Flag(pos) => self.flag(self.check(pos)?),
+            Command::Around(pos) => Ok(self.around(self.check(pos)?)),
+            Command::Row(i) => {
+                let height = self.sweeper.height();
+                if i >= height {
+                    return Err(format!(
+                        "There is no row {}, rows go from 1 to {}",
+                        i + 1,
+                        height
+                    ));
+                }
+                let cells = (0..self.sweeper.width()).map(|j| Pos::new(i, j));
+                Ok(format!("Row {}: {}", i + 1, self.read_line(cells)))
+            }
+            Command::Column(j) => {
+                let width = self.sweeper.width();
+                if j >= width {
+                    return Err(format!(
+                        "There is no column {}, columns go from a to {}",
+                        column_name(j),
+                        column_name(width - 1)
+                    ));
+                }
+                let cells = (0..self.sweeper.height()).map(|i| Pos::new(i, j));
+                Ok(format!(
+                    "Column {}: {}",
+                    column_name(j),
+                    self.read_line(cells)
+                ))
+            }
+            Command::Status => Ok(self.status()),
+            Command::Hint => {
+                self.playing()?;
+                let hint = sweeprs::hint(&self.sweeper).ok_or("Nothing left to open")?;
+                self.hints += 1;
+                Ok(format!("Hint: {}", explain(&self.sweeper, &hint, name)))
+            }
+            Command::New(difficulty) => {
+                let level = difficulty.map_or(self.level, Level::Difficulty);
+                *self = Self {
+                    player: self.player.take(),
+                    ..Self::new(level).map_err(|e| e.to_string())?
+                };
+                Ok(self.intro())
+            }
+            Command::Help => Ok(HELP.to_owned()),
+            Command::Quit => Ok(String::new()),
+        }
+    }
+
+    fn check(&self, pos: Pos) -> Result<Pos, String> {
+        let (height, width) = (self.sweeper.height(), self.sweeper.width());
+        if pos.i < height && pos.j < width {
+            Ok(pos)
+        } else {
+            Err(format!(
+                "{} is outside of the board, columns go from a to {} and rows from 1 to {}",
+                name(pos),
+                column_name(width - 1),
+                height
+            ))
+        }
+    }
+
+    fn playing(&self) -> Result<(), String> {
+        match self.sweeper.state() {
+            BoardState::Finished(_) => Err("The game is over, type new to play again".to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    fn open(&mut self, pos: Pos) -> Result<String, String> {
+        self.playing()?;
+        let closed = self.sweeper.closed_count();
+        let reply = match self.sweeper.cell(pos).state() {
+            CellState::Flagged => return Err(format!("{} is flagged, unflag it first", name(pos))),
+            CellState::Opened => {
+                self.sweeper.open(pos);
+                match closed - self.sweeper.closed_count() {
+                    0 => self.around(pos),
+                    1 => format!("1 cell opened around {}", name(pos)),
+                    opened => format!("{} cells opened around {}", opened, name(pos)),
+                }
+            }
+            CellState::Closed => {
+                self.sweeper.open(pos);
+                self.started.get_or_insert_with(Instant::now);
+                match closed - self.sweeper.closed_count() {
+                    1 => format!("{} is {}", name(pos), self.describe(pos)),
+                    opened => format!(
+                        "{} is {}, {} cells opened",
+                        name(pos),
+                        self.describe(pos),
+                        opened
+                    ),
+                }
+            }
+        };
+        Ok(match self.finish() {
+            Some(end) => format!("{}. {}", reply, end),
+            None => reply,
+        })
+    }
+
+    fn flag(&mut self, pos: Pos) -> Result<String, String> {
+        self.playing()?;
+        let action = match self.sweeper.flag(pos) {
+            CellState::Flagged => "flagged",
+            CellState::Closed => "unflagged",
+            CellState::Opened => return Err(format!("{} is open", name(pos))),
+        };
+        Ok(format!(
+            "{} {}, {} mines left to flag",
+            name(pos),
+            action,
+            self.sweeper.remaining_flags()
+        ))
+    }
+
+    /// Stop the clock, record the game and tell how it ended, once it is
+    /// finished.
+    fn finish(&mut self) -> Option<String> {
+        let won = match self.sweeper.state() {
+            BoardState::Finished(BoardResult::Win) => true,
+            BoardState::Finished(BoardResult::Lost) => false,
+            _ => return None,
+        };
+        let time = self
+            .started
+            .map_or_else(Duration::default, |started| started.elapsed());
+        self.time = Some(time);
+        let mut end = if won {
+            format!(
+                "You win in {:.1} seconds, 3BV {}.",
+                time.as_secs_f64(),
+                self.sweeper.three_bv()
+            )
+        } else {
+            format!("You lost after {:.1} seconds.", time.as_secs_f64())
+        };
+        if let Some(player) = &self.player {
+            let record = scores::Record::finished(
+                self.level.name(),
+                won,
+                time,
+                self.sweeper.three_bv(),
+                self.hints,
+                player.clone(),
+            );
+            if let Some(rank) = scores::save_and_rank(&record) {
+                end.push_str(&format!(" {}.", rank));
+            }
+        }
+        end.push_str(" Type new to play again or quit to leave.");
+        Some(end)
+    }
+
+    /// What the cell shows, mines are only told once the game is over.
+    fn describe(&self, pos: Pos) -> String {
+        let cell = self.sweeper.cell(pos);
+        let finished = matches!(self.sweeper.state(), BoardState::Finished(_));
+        match (cell.state(), cell.kind()) {
+            (CellState::Opened, CellKind::Mine) => "a mine".to_owned(),
+            (_, CellKind::Mine) if finished => "a mine".to_owned(),
+            (CellState::Closed, _) => "closed".to_owned(),
+            (CellState::Flagged, _) => "flagged".to_owned(),
+            (CellState::Opened, _) => match self.sweeper.count_adjacent_mines(pos) {
+                0 => "empty".to_owned(),
+                mine_count => mine_count.to_string(),
+            },
+        }
+    }
+
+    /// The cell and how many of its neighbours are closed or flagged, open
+    /// neighbours can be read one by one.
+    fn around(&self, pos: Pos) -> String {
+        let of_state = |wanted: fn(&CellState) -> bool| -> Vec<String> {
+            self.sweeper
+                .neighbours(pos)
+                .filter(|&nbr| wanted(self.sweeper.cell(nbr).state()))
+                .map(name)
+                .collect()
+        };
+        let closed = of_state(|state| matches!(state, CellState::Closed));
+        let flagged = of_state(|state| matches!(state, CellState::Flagged));
+        let mut neighbours = Vec::new();
+        for (names, what) in [(closed, "closed"), (flagged, "flagged")] {
+            if !names.is_empty() {
+                neighbours.push(format!("{} {} ({})", names.len(), what, names.join(", ")));
+            }
+        }
+        if neighbours.is_empty() {
+            neighbours.push("all open".to_owned());
+        }
+        format!(
+            "{} is {}, neighbours: {}",
+            name(pos),
+            self.describe(pos),
+            neighbours.join(", ")
+        )
+    }
+
+    /// Cells in order, with runs of cells showing the same thing grouped
+    /// so long empty or closed stretches read quickly.
+    fn read_line(&self, cells: impl Iterator<Item = Pos>) -> String {
+        let mut runs: Vec<(Pos, Pos, String)> = Vec::new();
+        for pos in cells {
+            let what = self.describe(pos);
+            match runs.last_mut() {
+                Some((_, last, run)) if *run == what => *last = pos,
+                _ => runs.push((pos, pos, what)),
+            }
+        }
+        let runs: Vec<String> = runs
+            .into_iter()
+            .map(|(first, last, what)| match first == last {
+                true => format!("{} {}", name(first), what),
+                false => format!("{} to {} {}", name(first), name(last), what),
+            })
+            .collect();
+        runs.join(", ")
+    }
+
+    fn status(&self) -> String {
+        let closed = self.sweeper.closed_count();
+        let time = self.time.unwrap_or_else(|| {
+            self.started
+                .map_or_else(Duration::default, |started| started.elapsed())
+        });
+        let mut status = format!(
+            "{} mines left to flag, {} cells closed, {} seconds",
+            self.sweeper.remaining_flags(),
+            closed,
+            time.as_secs()
+        );
+        match self.hints {
+            0 => (),
+            1 => status.push_str(", 1 hint used"),
+            hints => status.push_str(&format!(", {} hints used", hints)),
+        }
+        match self.sweeper.state() {
+            BoardState::Finished(BoardResult::Win) => status.push_str(". You won"),
+            BoardState::Finished(BoardResult::Lost) => status.push_str(". You lost"),
+            _ => (),
+        }
+        status
+    }
+}
+
+/// Spreadsheet style name of a cell, the column letters then the row
+/// counted from 1, e.g. `c5` or `aa12`.
+pub fn name(pos: Pos) -> String {
+    format!("{}{}", column_name(pos.j), pos.i + 1)
+}
+
+/// `a` to `z`, then `aa`, `ab` and so on.
+fn column_name(mut j: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push((b'a' + (j % 26) as u8) as char);
+        if j < 26 {
+            break;
+        }
+        j = j / 26 - 1;
+    }
+    letters.iter().rev().collect()
+}
+
+fn parse_column(letters: &str) -> Option<usize> {
+    let column = letters.chars().try_fold(0usize, |column, letter| {
+        let digit = (letter as usize).checked_sub('a' as usize)?;
+        if digit >= 26 {
+            return None;
+        }
+        column.checked_mul(26)?.checked_add(digit + 1)
+    })?;
+    column.checked_sub(1)
+}
+
+fn parse_pos(word: &str) -> Result<Pos, String> {
+    let invalid = || {
+        format!(
+            "`{}` is not a cell, write the column then the row like c5",
+            word
+        )
+    };
+    let split = word
+        .find(|c: char| c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (letters, digits) = word.split_at(split);
+    let j = parse_column(letters).ok_or_else(invalid)?;
+    let row: usize = digits.parse().map_err(|_| invalid())?;
+    let i = row.checked_sub(1).ok_or_else(invalid)?;
+    Ok(Pos::new(i, j))
+}
+
+fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim().to_lowercase();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = match words.as_slice() {
+        ["open" | "o", pos] => Command::Open(parse_pos(pos)?),
+        ["flag" | "f", pos] => Command::Flag(parse_pos(pos)?),
+        ["read" | "r", "row", row] => {
+            let row: usize = row
+                .parse()
+                .map_err(|_| format!("`{}` is not a row number", row))?;
+            Command::Row(row.checked_sub(1).ok_or("Rows start from 1")?)
+        }
+        ["read" | "r", "column", column] => Command::Column(
+            parse_column(column).ok_or_else(|| format!("`{}` is not a column", column))?,
+        ),
+        ["read" | "r", "around", pos] | ["read" | "r", pos] => Command::Around(parse_pos(pos)?),
+        ["status" | "s"] => Command::Status,
+        ["hint" | "h"] => Command::Hint,
+        ["new"] => Command::New(None),
+        ["new", "easy"] => Command::New(Some(Difficulty::Easy)),
+        ["new", "medium"] => Command::New(Some(Difficulty::Medium)),
+        ["new", "hard"] => Command::New(Some(Difficulty::Hard)),
+        ["help" | "?"] => Command::Help,
+        ["quit" | "q" | "exit"] => Command::Quit,
+        _ => {
+            return Err(format!(
+                "Unknown command `{}`, type help for the list",
+                line
+            ))
+        }
+    };
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use sweeprs::Board;
+
+    use super::*;
+
+    #[test]
+    fn column_names() {
+        for (j, letters) in [(0, "a"), (25, "z"), (26, "aa"), (701, "zz"), (702, "aaa")] {
+            assert_eq!(column_name(j), letters);
+            assert_eq!(parse_column(letters), Some(j));
+        }
+        assert_eq!(parse_column(""), None);
+        assert_eq!(parse_column("a1"), None);
+        assert_eq!(name(Pos::new(4, 2)), "c5");
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parse("open c5"), Ok(Command::Open(Pos::new(4, 2))));
+        assert_eq!(parse("  F  AA12 "), Ok(Command::Flag(Pos::new(11, 26))));
+        assert_eq!(parse("read row 3"), Ok(Command::Row(2)));
+        assert_eq!(parse("read column c"), Ok(Command::Column(2)));
+        assert_eq!(parse("read around c5"), parse("read c5"));
+        assert_eq!(parse("new hard"), Ok(Command::New(Some(Difficulty::Hard))));
+        assert!(parse("open c0").is_err());
+        assert!(parse("open 5c").is_err());
+        assert!(parse("read row 0").is_err());
+        assert!(parse("dance").is_err());
+    }
+
+    #[test]
+    fn play_lines() {
+        let mut game =
+            AccessibleGame::<Board>::with_seed(Level::Difficulty(Difficulty::Easy), 3).unwrap();
+        let input = "open e5\nread around e5\nflag a1\n\nflag a1\nopen j1\nread row 5\n\
+                     status\nhint\nquit\nopen a1\n";
+        let mut output = Vec::new();
+        game.run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].starts_with("New easy game, 9 columns from a to i"));
+        assert_eq!(lines[1], "e5 is empty, 26 cells opened");
+        assert_eq!(lines[2], "e5 is empty, neighbours: all open");
+        assert_eq!(lines[3], "a1 flagged, 9 mines left to flag");
+        assert_eq!(lines[4], "a1 unflagged, 10 mines left to flag");
+        assert!(lines[5].starts_with("j1 is outside of the board"));
+        assert_eq!(lines[6], "Row 5: a5 to c5 closed, d5 2, e5 to i5 empty");
+        assert!(lines[7].starts_with("10 mines left to flag, 55 cells closed"));
+        assert_eq!(
+            lines[8],
+            "Hint: g3 is safe, the 2 at h4 already touches all its mines"
+        );
+        assert_eq!(game.hints, 1);
+    }
+}
@@ -0,0 +1,569 @@
+use std::{
+    convert::TryFrom,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::theme::{GlyphSet, Glyphs, Theme};
+
+/// Everything that can be customised from `config.toml`. Missing fields
+/// take their default value, theme and glyphs are picked from what the
+/// terminal supports when left out.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub difficulty: Difficulty,
+    pub theme: Option<Theme>,
+    pub glyphs: Option<Glyphs>,
+    pub keys: Keys,
+    pub race: Race,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::Easy,
+            theme: None,
+            glyphs: None,
+            keys: Keys::default(),
+            race: Race::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Height, width and mine count of the board.
+    pub fn dimension(self) -> (usize, usize, usize) {
+        match self {
+            Self::Easy => (9, 9, 10),
+            Self::Medium => (16, 16, 40),
+            Self::Hard => (24, 24, 99),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Easy => "easy",
+            Self::Medium => "medium",
+            Self::Hard => "hard",
+        }
+    }
+}
+
+/// Board of a game, one of the difficulties or a custom size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Difficulty(Difficulty),
+    Custom {
+        height: usize,
+        width: usize,
+        mine_count: usize,
+    },
+}
+
+impl Level {
+    /// Height, width and mine count of the board.
+    pub fn dimension(self) -> (usize, usize, usize) {
+        match self {
+            Self::Difficulty(difficulty) => difficulty.dimension(),
+            Self::Custom {
+                height,
+                width,
+                mine_count,
+            } => (height, width, mine_count),
+        }
+    }
+
+    /// Name the high scores are grouped by, e.g. `easy` or
+    /// `custom 30x16 99`.
+    pub fn name(self) -> String {
+        match self {
+            Self::Difficulty(difficulty) => difficulty.name().to_owned(),
+            Self::Custom {
+                height,
+                width,
+                mine_count,
+            } => format!("custom {}x{} {}", height, width, mine_count),
+        }
+    }
+}
+
+/// What a key press does in game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Open,
+    Flag,
+    /// Ask the solver for a safe cell.
+    Hint,
+    Quit,
+    /// Open or close the menu.
+    Menu,
+    /// Stop the clock and hide the board.
+    Pause,
+}
+
+/// Starting point of the key bindings, individual actions can still be
+/// rebound on top of it.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// Arrow keys to move, `a` to open and `s` to flag.
+    Arrows,
+    /// `hjkl` to move, space to open and `f` to flag.
+    Vim,
+    /// `wasd` to move, `j` to open and `k` to flag.
+    Wasd,
+}
+
+impl Preset {
+    pub const NAMES: &'static [&'static str] = &["arrows", "vim", "wasd"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "arrows" => Some(Self::Arrows),
+            "vim" => Some(Self::Vim),
+            "wasd" => Some(Self::Wasd),
+            _ => None,
+        }
+    }
+
+    fn keys(self, action: Action) -> Vec<Key> {
+        use KeyCode::{Char, Down, Esc, Left, Right, Up};
+        let codes = match (self, action) {
+            (_, Action::Quit) => vec![Char('q')],
+            (_, Action::Hint) => vec![Char('?')],
+            (_, Action::Menu) => vec![Esc, Char('m')],
+            (_, Action::Pause) => vec![Char('p')],
+            (Self::Arrows, Action::Up) => vec![Up],
+            (Self::Arrows, Action::Down) => vec![Down],
+            (Self::Arrows, Action::Left) => vec![Left],
+            (Self::Arrows, Action::Right) => vec![Right],
+            (Self::Arrows, Action::Open) => vec![Char('a')],
+            (Self::Arrows, Action::Flag) => vec![Char('s')],
+            (Self::Vim, Action::Up) => vec![Char('k'), Up],
+            (Self::Vim, Action::Down) => vec![Char('j'), Down],
+            (Self::Vim, Action::Left) => vec![Char('h'), Left],
+            (Self::Vim, Action::Right) => vec![Char('l'), Right],
+            (Self::Vim, Action::Open) => vec![Char(' ')],
+            (Self::Vim, Action::Flag) => vec![Char('f')],
+            (Self::Wasd, Action::Up) => vec![Char('w'), Up],
+            (Self::Wasd, Action::Down) => vec![Char('s'), Down],
+            (Self::Wasd, Action::Left) => vec![Char('a'), Left],
+            (Self::Wasd, Action::Right) => vec![Char('d'), Right],
+            (Self::Wasd, Action::Open) => vec![Char('j')],
+            (Self::Wasd, Action::Flag) => vec![Char('k')],
+        };
+        codes.into_iter().map(Key).collect()
+    }
+}
+
+/// Key bindings. Actions left out use the keys of the preset, `resolve`
+/// fills them in.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keys {
+    pub preset: Preset,
+    pub up: Option<Vec<Key>>,
+    pub down: Option<Vec<Key>>,
+    pub left: Option<Vec<Key>>,
+    pub right: Option<Vec<Key>>,
+    pub open: Option<Vec<Key>>,
+    pub flag: Option<Vec<Key>>,
+    pub hint: Option<Vec<Key>>,
+    pub quit: Option<Vec<Key>>,
+    pub menu: Option<Vec<Key>>,
+    pub pause: Option<Vec<Key>>,
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        Self {
+            preset: Preset::Arrows,
+            up: None,
+            down: None,
+            left: None,
+            right: None,
+            open: None,
+            flag: None,
+            hint: None,
+            quit: None,
+            menu: None,
+            pause: None,
+        }
+    }
+}
+
+impl Keys {
+    fn bindings(&self) -> [(Action, &Option<Vec<Key>>); 10] {
+        [
+            (Action::Up, &self.up),
+            (Action::Down, &self.down),
+            (Action::Left, &self.left),
+            (Action::Right, &self.right),
+            (Action::Open, &self.open),
+            (Action::Flag, &self.flag),
+            (Action::Hint, &self.hint),
+            (Action::Quit, &self.quit),
+            (Action::Menu, &self.menu),
+            (Action::Pause, &self.pause),
+        ]
+    }
+
+    /// Bind every action that is not explicitly set to the preset keys.
+    pub fn resolve(&mut self) {
+        let preset = self.preset;
+        for (action, keys) in [
+            (Action::Up, &mut self.up),
+            (Action::Down, &mut self.down),
+            (Action::Left, &mut self.left),
+            (Action::Right, &mut self.right),
+            (Action::Open, &mut self.open),
+            (Action::Flag, &mut self.flag),
+            (Action::Hint, &mut self.hint),
+            (Action::Quit, &mut self.quit),
+            (Action::Menu, &mut self.menu),
+            (Action::Pause, &mut self.pause),
+        ] {
+            keys.get_or_insert_with(|| preset.keys(action));
+        }
+    }
+
+    /// Action bound to the key, the first matching action wins when a key
+    /// is bound twice.
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings().iter().find_map(|(action, keys)| {
+            let keys = keys.as_ref()?;
+            keys.iter().any(|key| key.0 == code).then_some(*action)
+        })
+    }
+}
+
+/// Actions a race player has keys for.
+const RACE_ACTIONS: [Action; 6] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Open,
+    Action::Flag,
+];
+
+/// Keys of the two players of a race, only moving, opening and flagging
+/// are read from them. Quitting, the menu and pausing use `[keys]`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(from = "RaceFile")]
+pub struct Race {
+    pub left: Keys,
+    pub right: Keys,
+}
+
+impl Race {
+    /// A key pressed by one player must not play for the other one.
+    fn check(&self) -> Result<(), String> {
+        let bound = |keys: &Keys| {
+            let mut keys = keys.clone();
+            keys.resolve();
+            keys.bindings()
+                .into_iter()
+                .filter(|(action, _)| RACE_ACTIONS.contains(action))
+                .flat_map(|(_, keys)| keys.clone().unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+        let right = bound(&self.right);
+        match bound(&self.left)
+            .into_iter()
+            .find(|key| right.contains(key))
+        {
+            Some(key) => Err(format!("`{}` is bound for both race players", key)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `[race]` as written in the config file.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RaceFile {
+    left: PlayerKeys,
+    right: PlayerKeys,
+}
+
+impl From<RaceFile> for Race {
+    fn from(file: RaceFile) -> Self {
+        let Race { left, right } = Race::default();
+        Self {
+            left: file.left.onto(left),
+            right: file.right.onto(right),
+        }
+    }
+}
+
+/// Keys of one race player as written in the config file. Without a
+/// preset the actions left out keep the default keys of that side.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PlayerKeys {
+    preset: Option<Preset>,
+    up: Option<Vec<Key>>,
+    down: Option<Vec<Key>>,
+    left: Option<Vec<Key>>,
+    right: Option<Vec<Key>>,
+    open: Option<Vec<Key>>,
+    flag: Option<Vec<Key>>,
+    hint: Option<Vec<Key>>,
+    quit: Option<Vec<Key>>,
+    menu: Option<Vec<Key>>,
+    pause: Option<Vec<Key>>,
+}
+
+impl PlayerKeys {
+    fn onto(self, side: Keys) -> Keys {
+        let base = match self.preset {
+            Some(preset) => Keys {
+                preset,
+                ..Keys::default()
+            },
+            None => side,
+        };
+        Keys {
+            preset: base.preset,
+            up: self.up.or(base.up),
+            down: self.down.or(base.down),
+            left: self.left.or(base.left),
+            right: self.right.or(base.right),
+            open: self.open.or(base.open),
+            flag: self.flag.or(base.flag),
+            hint: self.hint.or(base.hint),
+            quit: self.quit.or(base.quit),
+            menu: self.menu.or(base.menu),
+            pause: self.pause.or(base.pause),
+        }
+    }
+}
+
+impl Default for Race {
+    /// `wasd` with `f` and `g` on the left, arrows with `.` and `/` on the
+    /// right, so both fit on one keyboard.
+    fn default() -> Self {
+        let keys = |preset, [up, down, left, right, open, flag]: [KeyCode; 6]| Keys {
+            preset,
+            up: Some(vec![Key(up)]),
+            down: Some(vec![Key(down)]),
+            left: Some(vec![Key(left)]),
+            right: Some(vec![Key(right)]),
+            open: Some(vec![Key(open)]),
+            flag: Some(vec![Key(flag)]),
+            ..Keys::default()
+        };
+        use KeyCode::{Char, Down, Left, Right, Up};
+        Self {
+            left: keys(
+                Preset::Wasd,
+                [
+                    Char('w'),
+                    Char('s'),
+                    Char('a'),
+                    Char('d'),
+                    Char('f'),
+                    Char('g'),
+                ],
+            ),
+            right: keys(
+                Preset::Arrows,
+                [Up, Down, Left, Right, Char('.'), Char('/')],
+            ),
+        }
+    }
+}
+
+/// A key as written in the config file, either a single character or the
+/// name of a special key such as `up` or `space`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key(pub KeyCode);
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, String> {
+        let code = match name.as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "esc" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key `{}`", name)),
+                }
+            }
+        };
+        Ok(Key(code))
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> String {
+        key.to_string()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            _ => f.write_str("unknown"),
+        }
+    }
+}
+
+impl Config {
+    /// Fill in everything left out with the defaults, so the config shows
+    /// what is actually used.
+    pub fn resolve(&mut self) {
+        self.theme.get_or_insert_with(Theme::detect);
+        self.glyphs.get_or_insert_with(Glyphs::detect);
+        self.keys.resolve();
+        self.race.left.resolve();
+        self.race.right.resolve();
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme.unwrap_or(Theme::Classic)
+    }
+
+    pub fn glyphs(&self) -> &'static GlyphSet {
+        self.glyphs.unwrap_or(Glyphs::Unicode).set()
+    }
+
+    /// `$XDG_CONFIG_HOME/sweeprs/config.toml`, falling back to
+    /// `~/.config/sweeprs/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_home.join("sweeprs").join("config.toml"))
+    }
+
+    /// Read the config file at `path`, or at the default location when no
+    /// path is given. Only a missing default file falls back to the
+    /// default config, any other problem is reported.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content)
+                .map_err(|e| format!("invalid config file {}: {}", path.display(), e)),
+            Err(_) if !required && !path.exists() => Ok(Self::default()),
+            Err(e) => Err(format!("cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(content).map_err(|e| e.to_string())?;
+        config.race.check()?;
+        Ok(config)
+    }
+
+    /// The effective configuration as TOML.
+    pub fn dump(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file() {
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn override_preset() {
+        let mut config = Config::parse(
+            "difficulty = \"hard\"\n\
+             [keys]\n\
+             preset = \"vim\"\n\
+             open = [\"space\", \"o\"]\n",
+        )
+        .unwrap();
+        config.keys.resolve();
+        assert_eq!(config.difficulty, Difficulty::Hard);
+        assert_eq!(config.keys.action(KeyCode::Char('k')), Some(Action::Up));
+        assert_eq!(config.keys.action(KeyCode::Up), Some(Action::Up));
+        assert_eq!(config.keys.action(KeyCode::Char('o')), Some(Action::Open));
+        assert_eq!(config.keys.action(KeyCode::Char('a')), None);
+        assert_eq!(config.keys.action(KeyCode::Char('?')), Some(Action::Hint));
+    }
+
+    #[test]
+    fn race_keys() {
+        let mut config = Config::parse("[race.right]\nopen = [\"enter\"]\n").unwrap();
+        config.resolve();
+        let Race { left, right } = &config.race;
+        assert_eq!(left.action(KeyCode::Char('a')), Some(Action::Left));
+        assert_eq!(left.action(KeyCode::Up), None);
+        assert_eq!(right.action(KeyCode::Enter), Some(Action::Open));
+        // the rest of the right player keeps the default race keys
+        assert_eq!(right.action(KeyCode::Up), Some(Action::Up));
+        assert_eq!(right.action(KeyCode::Char('/')), Some(Action::Flag));
+        assert_eq!(right.action(KeyCode::Char('.')), None);
+        assert_eq!(right.action(KeyCode::Char('s')), None);
+        // `a` and `s` already move the left player
+        assert!(Config::parse("[race.right]\npreset = \"arrows\"\n").is_err());
+        assert!(Config::parse("[race.left]\nflag = [\"/\"]\n").is_err());
+    }
+
+    #[test]
+    fn invalid_file() {
+        assert!(Config::parse("difficulty = \"impossible\"").is_err());
+        assert!(Config::parse("[keys]\nup = [\"shift\"]").is_err());
+        assert!(Config::parse("colour = \"red\"").is_err());
+    }
+
+    #[test]
+    fn dump_round_trip() {
+        let mut config = Config::default();
+        config.keys.preset = Preset::Wasd;
+        config.resolve();
+        assert_eq!(Config::parse(&config.dump()).unwrap(), config);
+    }
+}
@@ -0,0 +1,1059 @@
+use std::{
+    io::stdout,
+    ops::{AddAssign, SubAssign},
+    panic,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::{
+    cursor,
+    event::{
+        poll, read, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+    },
+    execute,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
+};
+
+use sweeprs::{
+    BoardResult, BoardState, CellKind, CellState, Error, Hint, Pos, Reason, SweeperBoard,
+    SweeperCell,
+};
+
+use crate::{
+    config::{Action, Config, Level, Race},
+    menu::{Entry, Menu},
+    render::{Frame, Renderer, Style},
+    scores,
+    theme::{self, GlyphSet},
+    viewport::{Minimap, Viewport, BOARD_LEFT, BOARD_TOP},
+    W,
+};
+
+struct BoundedIndex {
+    index: usize,
+    max: usize,
+}
+
+impl AddAssign<usize> for BoundedIndex {
+    fn add_assign(&mut self, other: usize) {
+        let new = self.index.saturating_add(other);
+        *self = Self {
+            max: self.max,
+            index: if new < self.max { new } else { self.index },
+        };
+    }
+}
+
+impl SubAssign<usize> for BoundedIndex {
+    fn sub_assign(&mut self, other: usize) {
+        *self = Self {
+            max: self.max,
+            index: self.index.saturating_sub(other),
+        };
+    }
+}
+
+/// Names of the players of a race, by where their board is.
+const SIDES: [&str; 2] = ["Left", "Right"];
+
+/// Summary of a finished game.
+struct Outcome {
+    won: bool,
+    time: Duration,
+    three_bv: usize,
+    hints: usize,
+}
+
+/// Board of one player, with its cursor and the part of it on screen. A
+/// race has one per player, side by side.
+struct Player<B: SweeperBoard> {
+    i: BoundedIndex,
+    j: BoundedIndex,
+    sweeper: B,
+    view: Viewport,
+}
+
+impl<B: SweeperBoard> Player<B> {
+    fn new(sweeper: B) -> Self {
+        Self {
+            i: BoundedIndex {
+                index: 0,
+                max: sweeper.height(),
+            },
+            j: BoundedIndex {
+                index: 0,
+                max: sweeper.width(),
+            },
+            sweeper,
+            view: Viewport::default(),
+        }
+    }
+
+    /// Cursor back to the top left corner of the board, which may have
+    /// changed size.
+    fn reset(&mut self) {
+        self.i = BoundedIndex {
+            index: 0,
+            max: self.sweeper.height(),
+        };
+        self.j = BoundedIndex {
+            index: 0,
+            max: self.sweeper.width(),
+        };
+        self.view = Viewport::default();
+    }
+
+    fn cursor(&self) -> Pos {
+        Pos::new(self.i.index, self.j.index)
+    }
+
+    fn finished(&self) -> bool {
+        matches!(self.sweeper.state(), BoardState::Finished(_))
+    }
+}
+
+pub struct Game<'a, B: SweeperBoard> {
+    /// The first player also plays alone, hints, autoplay and the mouse
+    /// only apply to them.
+    players: Vec<Player<B>>,
+    /// Two players race on the same layout, the first to clear wins.
+    race: bool,
+    level: Level,
+    /// The board was restarted, its layout is already known.
+    replay: bool,
+    buttons: Buttons,
+    /// Shared by every player, starts with the first opened cell.
+    timer: Timer,
+    /// Hint on screen, until its cell is opened or flagged.
+    hint: Option<Hint>,
+    hints: usize,
+    /// Delay between the moves of the solver when it plays on its own.
+    autoplay: Option<Duration>,
+    next_move: Instant,
+    /// Name finished games are recorded under, games are not recorded
+    /// without one.
+    player: Option<String>,
+    menu: Option<Menu>,
+    /// The board is hidden and the clock stopped until a key is pressed.
+    paused: bool,
+    quit: bool,
+    renderer: Renderer,
+    config: &'a Config,
+    w: &'a mut W,
+}
+
+impl<'a, B: SweeperBoard> Game<'a, B> {
+    pub fn new(level: Level, config: &'a Config, w: &'a mut W) -> Result<Self, Error> {
+        let (height, width, mine_count) = level.dimension();
+        let sweeper = B::new(height, width, mine_count)?;
+        Ok(Self::with_players(
+            vec![Player::new(sweeper)],
+            level,
+            config,
+            w,
+        ))
+    }
+
+    /// Two players on one keyboard, each on their own board with the mines
+    /// placed from `seed`. The first cell opened is opened on both boards,
+    /// so they get the same layout.
+    pub fn race(level: Level, seed: u64, config: &'a Config, w: &'a mut W) -> Result<Self, Error> {
+        let (height, width, mine_count) = level.dimension();
+        let players = (0..2)
+            .map(|_| B::with_seed(height, width, mine_count, seed).map(Player::new))
+            .collect::<Result<_, _>>()?;
+        let mut game = Self::with_players(players, level, config, w);
+        game.race = true;
+        Ok(game)
+    }
+
+    fn with_players(
+        players: Vec<Player<B>>,
+        level: Level,
+        config: &'a Config,
+        w: &'a mut W,
+    ) -> Self {
+        Self {
+            players,
+            race: false,
+            level,
+            replay: false,
+            buttons: Buttons::default(),
+            timer: Timer::default(),
+            hint: None,
+            hints: 0,
+            autoplay: None,
+            next_move: Instant::now(),
+            player: None,
+            menu: None,
+            paused: false,
+            quit: false,
+            renderer: Renderer::default(),
+            config,
+            w,
+        }
+    }
+
+    /// Let the solver play, one step every `delay`. The player can only
+    /// quit or open the menu.
+    pub fn autoplay(&mut self, delay: Duration) {
+        self.autoplay = Some(delay);
+        self.next_move = Instant::now() + delay;
+    }
+
+    /// Save every finished game in the high scores under `name`.
+    pub fn record_as(&mut self, name: String) {
+        self.player = Some(name);
+    }
+
+    /// Play until the player quits, from the menu or with the quit key.
+    pub fn run(&mut self) -> crossterm::Result<()> {
+        restore_on_panic();
+        execute!(
+            self.w,
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableFocusChange,
+            cursor::Hide
+        )?;
+        enable_raw_mode()?;
+        self.draw()?;
+
+        while !self.quit {
+            // wake up every second so the clock keeps ticking on screen
+            let tick = 1000 - self.timer.elapsed().subsec_millis() as u64;
+            let mut timeout = Duration::from_millis(tick);
+            let autoplay = self.autoplay.is_some() && self.menu.is_none() && !self.paused;
+            if autoplay {
+                timeout = timeout.min(self.next_move.saturating_duration_since(Instant::now()));
+            }
+            let event = match poll(timeout) {
+                Ok(true) => read().map(Some),
+                Ok(false) if autoplay && Instant::now() >= self.next_move => {
+                    self.step();
+                    Ok(None)
+                }
+                Ok(false) => {
+                    self.draw()?;
+                    continue;
+                }
+                Err(e) => Err(e),
+            };
+            match event {
+                Ok(None) => (),
+                Ok(Some(Event::Key(ke))) if ke.kind == KeyEventKind::Release => (),
+                Ok(Some(Event::Key(ke))) => {
+                    let action = self.config.keys.action(ke.code);
+                    if self.paused {
+                        match action {
+                            Some(Action::Quit) => self.quit = true,
+                            _ => self.resume(),
+                        }
+                    } else if self.menu.is_some() {
+                        self.menu_key(ke.code, action);
+                    } else {
+                        self.key(ke.code, action);
+                    }
+                }
+                Ok(Some(Event::Mouse(me))) if self.paused => {
+                    if let MouseEventKind::Down(_) = me.kind {
+                        self.resume();
+                    }
+                }
+                Ok(Some(Event::Mouse(me))) if self.menu.is_some() => self.menu_click(me),
+                // one mouse cannot be shared fairly in a race
+                Ok(Some(Event::Mouse(_))) if self.autoplay.is_some() || self.race => (),
+                Ok(Some(Event::Mouse(me))) => self.click(me),
+                Ok(Some(Event::Resize(_, _))) => self.renderer.invalidate(),
+                Ok(Some(Event::FocusLost)) => self.pause(),
+                Ok(Some(Event::FocusGained)) | Ok(Some(Event::Paste(_))) => (),
+                Err(e) => {
+                    self.tear_down().ok();
+                    panic!("{}", e)
+                }
+            }
+            let first = &self.players[0];
+            let hint_done = self.hint.as_ref().is_some_and(|hint| {
+                !matches!(first.sweeper.cell(hint.pos).state(), CellState::Closed)
+            }) || first.finished();
+            if hint_done {
+                self.hint = None;
+            }
+            if self.finished() {
+                self.timer.start();
+                self.timer.stop();
+                if self.menu.is_none() {
+                    self.finish();
+                }
+            } else if self
+                .players
+                .iter()
+                .any(|player| matches!(player.sweeper.state(), BoardState::Playing))
+            {
+                self.timer.start();
+            }
+            self.draw()?;
+        }
+        self.tear_down()
+    }
+
+    /// Alone the game ends with the board, a race ends as soon as one of
+    /// the boards is cleared or lost.
+    fn finished(&self) -> bool {
+        self.players.iter().any(Player::finished)
+    }
+
+    fn key(&mut self, code: KeyCode, action: Option<Action>) {
+        if self.race {
+            let Race { left, right } = &self.config.race;
+            for (index, keys) in [left, right].into_iter().enumerate() {
+                if let Some(
+                    action @ (Action::Up
+                    | Action::Down
+                    | Action::Left
+                    | Action::Right
+                    | Action::Open
+                    | Action::Flag),
+                ) = keys.action(code)
+                {
+                    return self.play(index, action);
+                }
+            }
+        }
+        match action {
+            Some(Action::Quit) => self.quit = true,
+            Some(Action::Menu) => {
+                self.menu = Some(Menu::new(Vec::new(), self.level, true));
+            }
+            Some(Action::Pause) => self.pause(),
+            _ if self.autoplay.is_some() || self.race => (),
+            Some(Action::Hint) => self.hint(),
+            Some(action) => self.play(0, action),
+            None => (),
+        }
+    }
+
+    /// Move, open or flag on the board of the player at `index`.
+    fn play(&mut self, index: usize, action: Action) {
+        let player = &mut self.players[index];
+        match action {
+            Action::Open => {
+                let pos = player.cursor();
+                self.open(index, pos);
+            }
+            Action::Flag => {
+                let pos = player.cursor();
+                player.sweeper.flag(pos);
+            }
+            Action::Up => player.i -= 1,
+            Action::Down => player.i += 1,
+            Action::Left => player.j -= 1,
+            Action::Right => player.j += 1,
+            _ => (),
+        }
+    }
+
+    /// Open a cell of the board at `index`. In a race the first cell is
+    /// opened on every board so the mines end up at the same places.
+    fn open(&mut self, index: usize, pos: Pos) {
+        let first = matches!(
+            self.players[index].sweeper.state(),
+            BoardState::Uninitialized
+        );
+        for (other, player) in self.players.iter_mut().enumerate() {
+            if other == index || (first && self.race) {
+                player.sweeper.open(pos);
+            }
+        }
+    }
+
+    /// Only a game going on can be paused, and not from the menu.
+    fn pause(&mut self) {
+        let playing = self
+            .players
+            .iter()
+            .any(|player| matches!(player.sweeper.state(), BoardState::Playing));
+        if playing && !self.finished() && self.menu.is_none() {
+            self.paused = true;
+            self.timer.pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        self.timer.resume();
+        self.next_move = Instant::now() + self.autoplay.unwrap_or_default();
+    }
+
+    /// Up and down move in the menu, the open key or enter picks the
+    /// entry. The menu key only closes it while the game is going on.
+    fn menu_key(&mut self, code: KeyCode, action: Option<Action>) {
+        let finished = self.finished();
+        let Some(menu) = &mut self.menu else {
+            return;
+        };
+        let selected = menu.selected();
+        match action {
+            Some(Action::Up) => menu.up(),
+            Some(Action::Down) => menu.down(),
+            Some(Action::Open) => self.pick(selected),
+            Some(Action::Quit) => self.quit = true,
+            Some(Action::Menu) if !finished => self.menu = None,
+            _ if code == KeyCode::Enter => self.pick(selected),
+            _ => (),
+        }
+    }
+
+    fn menu_click(&mut self, me: MouseEvent) {
+        if let MouseEventKind::Down(MouseButton::Left) = me.kind {
+            let entry = self
+                .menu
+                .as_mut()
+                .and_then(|menu| menu.entry_at(me.column, me.row));
+            if let Some(entry) = entry {
+                self.pick(entry);
+            }
+        }
+    }
+
+    fn pick(&mut self, entry: Entry) {
+        match entry {
+            Entry::Resume => self.menu = None,
+            Entry::NewGame => self.new_game(self.level),
+            Entry::Restart => {
+                self.replay = !matches!(self.players[0].sweeper.state(), BoardState::Uninitialized);
+                for player in &mut self.players {
+                    player.sweeper.restart();
+                }
+                self.reset();
+            }
+            Entry::Level(level) => self.new_game(level),
+            Entry::Quit => self.quit = true,
+        }
+    }
+
+    /// Fresh boards, with a new seed shared by every player in a race.
+    fn new_game(&mut self, level: Level) {
+        let (height, width, mine_count) = level.dimension();
+        let seed = time_seed();
+        let sweepers: Result<Vec<B>, Error> = (0..self.players.len())
+            .map(|_| match self.race {
+                true => B::with_seed(height, width, mine_count, seed),
+                false => B::new(height, width, mine_count),
+            })
+            .collect();
+        if let Ok(sweepers) = sweepers {
+            for (player, sweeper) in self.players.iter_mut().zip(sweepers) {
+                player.sweeper = sweeper;
+            }
+            self.level = level;
+            self.replay = false;
+            self.reset();
+        }
+    }
+
+    /// Everything but the boards back to how it is at the start of a game.
+    fn reset(&mut self) {
+        for player in &mut self.players {
+            player.reset();
+        }
+        self.buttons = Buttons::default();
+        self.timer = Timer::default();
+        self.hint = None;
+        self.hints = 0;
+        self.next_move = Instant::now() + self.autoplay.unwrap_or_default();
+        self.menu = None;
+        self.paused = false;
+    }
+
+    /// Record the game and show the result above the menu.
+    fn finish(&mut self) {
+        if self.race {
+            return self.finish_race();
+        }
+        let sweeper = &self.players[0].sweeper;
+        let outcome = Outcome {
+            won: matches!(sweeper.state(), BoardState::Finished(BoardResult::Win)),
+            time: self.timer.elapsed(),
+            three_bv: sweeper.three_bv(),
+            hints: self.hints,
+        };
+        let seconds = outcome.time.as_secs_f64();
+        let mut text = vec![
+            if outcome.won { "You win" } else { "You lost" }.to_owned(),
+            format!(
+                "{:.3}s  3BV {}  {:.2} 3BV/s",
+                seconds,
+                outcome.three_bv,
+                outcome.three_bv as f64 / seconds.max(0.001)
+            ),
+        ];
+        match outcome.hints {
+            0 => (),
+            1 => text.push("1 hint used".to_owned()),
+            hints => text.push(format!("{} hints used", hints)),
+        }
+        text.extend(self.record(&outcome));
+        self.menu = Some(Menu::new(text, self.level, false));
+    }
+
+    /// The player who cleared their board wins, or the other one when a
+    /// mine was hit. Both boards finishing on the same move, as when the
+    /// shared first click clears them, is a draw. Races are not recorded.
+    fn finish_race(&mut self) {
+        if let Some(text) = self.race_result() {
+            self.menu = Some(Menu::new(text, self.level, false));
+        }
+    }
+
+    /// Lines telling who won the race, `None` while both are playing.
+    fn race_result(&self) -> Option<Vec<String>> {
+        let won = |index: usize| {
+            matches!(
+                self.players[index].sweeper.state(),
+                BoardState::Finished(BoardResult::Win)
+            )
+        };
+        let lost = |index: usize| {
+            matches!(
+                self.players[index].sweeper.state(),
+                BoardState::Finished(BoardResult::Lost)
+            )
+        };
+        let winners: Vec<usize> = (0..2)
+            .filter(|&index| won(index) || lost(1 - index))
+            .collect();
+        let time = format!("{:.3}s", self.timer.elapsed().as_secs_f64());
+        let text = match winners[..] {
+            [] => return None,
+            [winner] => {
+                let how = match won(winner) {
+                    true => "cleared the board",
+                    false => "the other player hit a mine",
+                };
+                vec![
+                    format!("{} player wins", SIDES[winner]),
+                    format!("{}, {}", time, how),
+                ]
+            }
+            _ => vec![
+                "Draw".to_owned(),
+                format!("{}, both boards finished together", time),
+            ],
+        };
+        Some(text)
+    }
+
+    /// Save the game in the high scores, and show them after a win.
+    fn record(&self, outcome: &Outcome) -> Vec<String> {
+        let Some(name) = self.player.clone() else {
+            return Vec::new();
+        };
+        if self.replay {
+            return vec!["Restarted boards are not recorded".to_owned()];
+        }
+        let record = scores::Record::finished(
+            self.level.name(),
+            outcome.won,
+            outcome.time,
+            outcome.three_bv,
+            outcome.hints,
+            name,
+        );
+        scores::save_and_show(&record)
+    }
+
+    /// Hint asked by the player. Every request counts, even when the same
+    /// hint is asked for again.
+    fn hint(&mut self) {
+        if self.show_hint() {
+            self.hints += 1;
+        }
+    }
+
+    /// Ask the solver for the next cell and move the cursor onto it,
+    /// `false` when there is nothing left to open.
+    fn show_hint(&mut self) -> bool {
+        let player = &mut self.players[0];
+        match sweeprs::hint(&player.sweeper) {
+            Some(hint) => {
+                player.i.index = hint.pos.i;
+                player.j.index = hint.pos.j;
+                self.hint = Some(hint);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Next move of the autoplay. The chosen cell is shown with its
+    /// explanation first and only opened on the following step.
+    fn step(&mut self) {
+        match self.hint.take() {
+            Some(hint) => self.open(0, hint.pos),
+            None => {
+                self.show_hint();
+            }
+        }
+        self.next_move = Instant::now() + self.autoplay.unwrap_or_default();
+    }
+
+    /// Left click opens, right click flags, middle click or pressing both
+    /// buttons chords. Opening happens on release so pressing the second
+    /// button in between turns the click into a chord instead.
+    fn click(&mut self, me: MouseEvent) {
+        let player = &mut self.players[0];
+        let target = player.view.cell_at(me.column, me.row);
+        if let (Some(pos), MouseEventKind::Down(_)) = (target, me.kind) {
+            player.i.index = pos.i;
+            player.j.index = pos.j;
+        }
+        match me.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.buttons.left = true;
+                if self.buttons.right {
+                    self.buttons.chorded = true;
+                    self.chord(target);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Right) => {
+                self.buttons.right = true;
+                if self.buttons.left {
+                    self.buttons.chorded = true;
+                    self.chord(target);
+                } else if let Some(pos) = target {
+                    player.sweeper.flag(pos);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Middle) => self.chord(target),
+            MouseEventKind::Up(MouseButton::Left) => {
+                if let (Some(pos), false) = (target, self.buttons.chorded) {
+                    if self.buttons.left {
+                        self.open(0, pos);
+                    }
+                }
+                self.buttons.left = false;
+            }
+            MouseEventKind::Up(MouseButton::Right) => self.buttons.right = false,
+            _ => (),
+        }
+        if !self.buttons.left && !self.buttons.right {
+            self.buttons.chorded = false;
+        }
+    }
+
+    /// Chording only applies to opened cells, opening closed cells with
+    /// it would be too easy to do by accident.
+    fn chord(&mut self, target: Option<Pos>) {
+        if let Some(pos) = target {
+            if let CellState::Opened = self.players[0].sweeper.cell(pos).state() {
+                self.open(0, pos);
+            }
+        }
+    }
+
+    /// Every player gets an equal share of the screen width.
+    fn draw(&mut self) -> crossterm::Result<()> {
+        let (columns, rows) = terminal::size().unwrap_or((80, 24));
+        let share = columns / self.players.len() as u16;
+        for player in &mut self.players {
+            let (height, width) = (player.sweeper.height(), player.sweeper.width());
+            player.view.fit(share, rows, height, width);
+            player.view.follow(player.cursor(), height, width);
+        }
+        let mut frame = Frame::new(columns as usize, rows as usize);
+        for index in 0..self.players.len() {
+            let x = index * share as usize;
+            self.draw_status(&mut frame, index, x);
+            self.draw_board(&mut frame, index, x);
+        }
+        if let Some(hint) = self.hint.as_ref().filter(|_| !self.paused) {
+            let y = BOARD_TOP + self.players[0].view.rows + 1;
+            let label = match self.autoplay {
+                Some(_) => "Autoplay",
+                None => "Hint",
+            };
+            let text = format!("{}: {}", label, self.explain(hint));
+            frame.put_str(0, y, &text, Style::default());
+        }
+        if let Some(menu) = &mut self.menu {
+            // next to the board so the result stays visible, centered when
+            // there is no room left
+            let (menu_width, menu_height) = menu.size();
+            let (columns, rows) = (columns as usize, rows as usize);
+            let view = self.players[0].view;
+            let x = BOARD_LEFT + view.columns * 2 + 2;
+            let origin = if !self.race && view.minimap.is_none() && x + menu_width <= columns {
+                (x, BOARD_TOP - 1)
+            } else {
+                (
+                    columns.saturating_sub(menu_width) / 2,
+                    rows.saturating_sub(menu_height) / 2,
+                )
+            };
+            menu.draw(&mut frame, self.config.glyphs(), origin);
+        }
+        self.renderer.render(self.w, frame)
+    }
+
+    /// Why the hinted cell was picked, positions are shown from 1 like in
+    /// the status line.
+    fn explain(&self, hint: &Hint) -> String {
+        explain(&self.players[0].sweeper, hint, |pos| {
+            format!("({}, {})", pos.i + 1, pos.j + 1)
+        })
+    }
+
+    /// Remaining mines, game face, elapsed seconds, cursor position and
+    /// the number of hints used, starting at column `x`. In a race the
+    /// line starts with the side of the player.
+    fn draw_status(&self, frame: &mut Frame, index: usize, x: usize) {
+        let player = &self.players[index];
+        let face = match player.sweeper.state() {
+            BoardState::Uninitialized | BoardState::Playing => ":)",
+            BoardState::Finished(BoardResult::Win) => "B)",
+            BoardState::Finished(BoardResult::Lost) => "X(",
+        };
+        let mut status = match self.race {
+            true => format!("{}  ", SIDES[index]),
+            false => String::new(),
+        };
+        status.push_str(&format!(
+            "{:03} {} {:03}  ({}, {})",
+            player.sweeper.remaining_flags(),
+            face,
+            self.timer.elapsed().as_secs(),
+            player.i.index + 1,
+            player.j.index + 1,
+        ));
+        if self.hints > 0 {
+            status.push_str(&format!("  hints: {}", self.hints));
+        }
+        frame.put_str(x, 0, &status, Style::default());
+    }
+
+    /// Draw the visible part of the board of the player at `index`, from
+    /// column `x`, with arrows on the borders pointing where it continues,
+    /// and the minimap on its right.
+    fn draw_board(&self, frame: &mut Frame, index: usize, x: usize) {
+        let glyphs = self.config.glyphs();
+        let theme = self.config.theme();
+        let player = &self.players[index];
+        let (sweeper, view) = (&player.sweeper, player.view);
+        let (height, width) = (sweeper.height(), sweeper.width());
+        let [up, down, left, right] = glyphs.arrows;
+        let top = BOARD_TOP - 1;
+        let right_border = x + BOARD_LEFT + view.columns * 2;
+        draw_box(
+            frame,
+            glyphs,
+            (x, top),
+            (right_border, top + view.rows + 1),
+            [
+                (view.top > 0).then_some(up),
+                (view.top + view.rows < height).then_some(down),
+                (view.left > 0).then_some(left),
+                (view.left + view.columns < width).then_some(right),
+            ],
+        );
+        if self.paused {
+            // nothing of the board, not even the minimap
+            let middle = BOARD_TOP + view.rows.saturating_sub(2) / 2;
+            for (y, text) in [(middle, "Paused"), (middle + 1, "press any key")] {
+                let column = (x + right_border + 1).saturating_sub(text.len()) / 2;
+                frame.put_str(column, y, text, Style::default());
+            }
+            return;
+        }
+        for row in 0..view.rows {
+            let i_idx = view.top + row;
+            for column in 0..view.columns {
+                let j_idx = view.left + column;
+                let cell = sweeper.cell(Pos::new(i_idx, j_idx));
+                let (ch, mut colour) =
+                    theme::cell(theme, glyphs, cell.kind(), cell.state(), || {
+                        sweeper.count_adjacent_mines(Pos::new(i_idx, j_idx))
+                    });
+                let mut cursor = false;
+                match sweeper.state() {
+                    BoardState::Uninitialized | BoardState::Playing => {
+                        cursor = Pos::new(i_idx, j_idx) == player.cursor();
+                    }
+                    BoardState::Finished(_) => {
+                        if let CellKind::Mine = cell.kind() {
+                            colour = theme.mine();
+                        }
+                    }
+                }
+                let mut style = theme.style(ch, colour, cursor);
+                if self
+                    .hint
+                    .as_ref()
+                    .is_some_and(|hint| index == 0 && hint.pos == Pos::new(i_idx, j_idx))
+                {
+                    style = theme.highlight(style);
+                }
+                frame.put(x + BOARD_LEFT + column * 2, BOARD_TOP + row, ch, style);
+            }
+        }
+        if let Some(minimap) = view.minimap {
+            self.draw_minimap(frame, player, minimap, right_border + 2);
+        }
+    }
+
+    /// Minimap box with its left border at column `x`. Blocks mostly
+    /// closed are drawn closed and the visible part is highlighted like the
+    /// cursor.
+    fn draw_minimap(&self, frame: &mut Frame, player: &Player<B>, minimap: Minimap, x: usize) {
+        let glyphs = self.config.glyphs();
+        let theme = self.config.theme();
+        let (sweeper, view) = (&player.sweeper, player.view);
+        let top = BOARD_TOP - 1;
+        draw_box(
+            frame,
+            glyphs,
+            (x, top),
+            (x + minimap.columns + 1, top + minimap.rows + 1),
+            [None; 4],
+        );
+        for line in 0..minimap.rows {
+            let rows = minimap.block_rows * line..minimap.block_rows * (line + 1);
+            let rows = rows.start..rows.end.min(sweeper.height());
+            for column in 0..minimap.columns {
+                let columns = minimap.block_columns * column..minimap.block_columns * (column + 1);
+                let columns = columns.start..columns.end.min(sweeper.width());
+                let closed = rows
+                    .clone()
+                    .flat_map(|i| columns.clone().map(move |j| Pos::new(i, j)))
+                    .filter(|&pos| !matches!(sweeper.cell(pos).state(), CellState::Opened))
+                    .count();
+                let closed = closed * 2 > rows.len() * columns.len();
+                let visible = rows.start < view.top + view.rows
+                    && view.top < rows.end
+                    && columns.start < view.left + view.columns
+                    && view.left < columns.end;
+                let ch = if closed { glyphs.closed } else { ' ' };
+                frame.put(
+                    x + 1 + column,
+                    BOARD_TOP + line,
+                    ch,
+                    theme.style(ch, None, visible),
+                );
+            }
+        }
+    }
+
+    fn tear_down(&mut self) -> crossterm::Result<()> {
+        disable_raw_mode()?;
+        execute!(
+            self.w,
+            DisableMouseCapture,
+            DisableFocusChange,
+            cursor::Show,
+            LeaveAlternateScreen
+        )?;
+        Ok(())
+    }
+}
+
+/// Seed for boards that need one but were not given any.
+pub fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// Why the solver picked the hinted cell, with positions written by
+/// `name`.
+pub fn explain<B: SweeperBoard>(board: &B, hint: &Hint, name: impl Fn(Pos) -> String) -> String {
+    let number = |pos: Pos| format!("the {} at {}", board.count_adjacent_mines(pos), name(pos));
+    let target = name(hint.pos);
+    match hint.reason {
+        Reason::FirstMove => {
+            format!("{} is safe, the first move never hits a mine", target)
+        }
+        Reason::Satisfied { number: pos } => format!(
+            "{} is safe, {} already touches all its mines",
+            target,
+            number(pos)
+        ),
+        Reason::Subset { inner, outer } => format!(
+            "{} is safe, {} has no mines left besides those around {}",
+            target,
+            number(outer),
+            number(inner)
+        ),
+        Reason::Enumeration => format!(
+            "{} is safe, no layout matching the numbers and mine count has a mine there",
+            target
+        ),
+        Reason::Guess { probability } => format!(
+            "nothing is certain, {} has the lowest chance of a mine ({:.0}%)",
+            target,
+            probability * 100.0
+        ),
+    }
+}
+
+/// Game clock, starts with the first opened cell and stops when the game
+/// is finished. Time spent paused is not counted.
+#[derive(Default)]
+struct Timer {
+    /// Time counted until the clock was last paused.
+    counted: Duration,
+    /// When the clock was last started or resumed, `None` while it is not
+    /// running.
+    running: Option<Instant>,
+    started: bool,
+    stopped: bool,
+}
+
+impl Timer {
+    fn start(&mut self) {
+        if !self.started {
+            self.started = true;
+            self.running = Some(Instant::now());
+        }
+    }
+
+    fn stop(&mut self) {
+        self.pause();
+        self.stopped = true;
+    }
+
+    fn pause(&mut self) {
+        if let Some(running) = self.running.take() {
+            self.counted += running.elapsed();
+        }
+    }
+
+    /// Only a clock that was started, and not stopped yet, can resume.
+    fn resume(&mut self) {
+        if self.started && !self.stopped && self.running.is_none() {
+            self.running = Some(Instant::now());
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.counted
+            + self
+                .running
+                .map_or_else(Duration::default, |running| running.elapsed())
+    }
+}
+
+/// Mouse buttons currently held down.
+#[derive(Default)]
+struct Buttons {
+    left: bool,
+    right: bool,
+    /// Both buttons were pressed together, releasing them must not open.
+    chorded: bool,
+}
+
+/// Box with corners at `top_left` and `bottom_right`, drawn over
+/// whatever is in the frame. The arrows go in the middle of the top,
+/// bottom, left and right borders.
+pub fn draw_box(
+    frame: &mut Frame,
+    glyphs: &GlyphSet,
+    top_left: (usize, usize),
+    bottom_right: (usize, usize),
+    arrows: [Option<char>; 4],
+) {
+    let ((left, top), (right, bottom)) = (top_left, bottom_right);
+    let style = Style::default();
+    for x in left + 1..right {
+        frame.put(x, top, glyphs.horizontal, style);
+        frame.put(x, bottom, glyphs.horizontal, style);
+    }
+    for y in top + 1..bottom {
+        frame.put(left, y, glyphs.vertical, style);
+        frame.put(right, y, glyphs.vertical, style);
+    }
+    let [top_left, top_right, bottom_left, bottom_right] = glyphs.corners;
+    frame.put(left, top, top_left, style);
+    frame.put(right, top, top_right, style);
+    frame.put(left, bottom, bottom_left, style);
+    frame.put(right, bottom, bottom_right, style);
+    let (middle_x, middle_y) = ((left + right) / 2, (top + bottom) / 2);
+    let [up, down, left_arrow, right_arrow] = arrows;
+    let positions = [
+        (up, middle_x, top),
+        (down, middle_x, bottom),
+        (left_arrow, left, middle_y),
+        (right_arrow, right, middle_y),
+    ];
+    for (arrow, x, y) in positions {
+        if let Some(arrow) = arrow {
+            frame.put(x, y, arrow, style);
+        }
+    }
+}
+
+/// Leave the terminal usable if anything panics while it is in raw mode.
+pub fn restore_on_panic() {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        disable_raw_mode().ok();
+        execute!(
+            stdout(),
+            DisableMouseCapture,
+            DisableFocusChange,
+            cursor::Show,
+            LeaveAlternateScreen
+        )
+        .ok();
+        hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufWriter, thread::sleep};
+
+    use sweeprs::Board;
+
+    use super::*;
+
+    #[test]
+    fn race_cleared_together_is_a_draw() {
+        let config = Config::default();
+        let mut w = BufWriter::new(stdout());
+        let level = Level::Custom {
+            height: 9,
+            width: 9,
+            mine_count: 0,
+        };
+        let mut game = Game::<Board>::race(level, 1, &config, &mut w).unwrap();
+        assert_eq!(game.race_result(), None);
+        game.open(1, Pos::new(4, 4));
+        let text = game.race_result().unwrap();
+        assert_eq!(text[0], "Draw");
+    }
+
+    #[test]
+    fn paused_time_is_not_counted() {
+        let mut timer = Timer::default();
+        timer.resume();
+        assert_eq!(timer.elapsed(), Duration::default());
+        timer.start();
+        sleep(Duration::from_millis(10));
+        timer.pause();
+        let paused = timer.elapsed();
+        assert!(paused >= Duration::from_millis(10));
+        sleep(Duration::from_millis(20));
+        assert_eq!(timer.elapsed(), paused);
+        timer.resume();
+        sleep(Duration::from_millis(10));
+        assert!(timer.elapsed() >= paused + Duration::from_millis(10));
+        timer.stop();
+        let stopped = timer.elapsed();
+        timer.resume();
+        sleep(Duration::from_millis(10));
+        assert_eq!(timer.elapsed(), stopped);
+    }
+}
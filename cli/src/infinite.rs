@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use crossterm::{
+    cursor,
+    event::{poll, read, Event, KeyEventKind},
+    execute,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
+};
+
+use sweeprs::{BoardState, CellKind, InfiniteBoard};
+
+use crate::{
+    config::{Action, Config},
+    game::{draw_box, restore_on_panic},
+    render::{Frame, Renderer, Style},
+    theme,
+    viewport::{BOARD_LEFT, BOARD_TOP},
+    W,
+};
+
+/// Cells kept between the cursor and the edge of the screen before the
+/// viewport starts following it.
+const SCROLL_MARGIN: i64 = 3;
+
+/// Endless game, the viewport scrolls with the cursor in every direction.
+pub struct InfiniteGame<'a> {
+    i: i64,
+    j: i64,
+    /// Board coordinate shown at the top left corner of the screen.
+    top: i64,
+    left: i64,
+    sweeper: InfiniteBoard,
+    renderer: Renderer,
+    config: &'a Config,
+    w: &'a mut W,
+}
+
+impl<'a> InfiniteGame<'a> {
+    pub fn new(sweeper: InfiniteBoard, config: &'a Config, w: &'a mut W) -> Self {
+        let (height, width) = viewport_size();
+        Self {
+            i: 0,
+            j: 0,
+            top: -height / 2,
+            left: -width / 2,
+            sweeper,
+            renderer: Renderer::default(),
+            config,
+            w,
+        }
+    }
+
+    pub fn run(&mut self) -> crossterm::Result<()> {
+        restore_on_panic();
+        execute!(self.w, EnterAlternateScreen, cursor::Hide)?;
+        enable_raw_mode()?;
+        self.draw()?;
+
+        loop {
+            if let BoardState::Finished(_) = self.sweeper.state() {
+                break;
+            }
+            match read() {
+                Ok(Event::Key(ke)) if ke.kind == KeyEventKind::Release => (),
+                Ok(Event::Key(ke)) => match self.config.keys.action(ke.code) {
+                    Some(Action::Quit) => break,
+                    Some(Action::Open) => {
+                        self.sweeper.open(self.i, self.j);
+                    }
+                    Some(Action::Flag) => {
+                        self.sweeper.flag(self.i, self.j);
+                    }
+                    Some(Action::Up) => self.i -= 1,
+                    Some(Action::Down) => self.i += 1,
+                    Some(Action::Left) => self.j -= 1,
+                    Some(Action::Right) => self.j += 1,
+                    // the solver needs a board with a known mine count, there
+                    // is a single endless board and no clock to stop
+                    Some(Action::Hint) | Some(Action::Menu) | Some(Action::Pause) | None => (),
+                },
+                Ok(Event::Resize(_, _)) => self.renderer.invalidate(),
+                Ok(_) => (),
+                Err(e) => {
+                    self.tear_down().ok();
+                    panic!("{}", e)
+                }
+            }
+            self.scroll();
+            self.draw()?;
+        }
+
+        if let BoardState::Finished(_) = self.sweeper.state() {
+            self.wait_for_key()?;
+        }
+        self.tear_down()?;
+        match self.sweeper.state() {
+            BoardState::Finished(_) => println!("You lost, {} cells cleared", self.sweeper.score()),
+            _ => println!("Game stopped, {} cells cleared", self.sweeper.score()),
+        }
+        Ok(())
+    }
+
+    /// Keep the board the game was lost on on screen, with the score over
+    /// it, until a key is pressed.
+    fn wait_for_key(&mut self) -> crossterm::Result<()> {
+        // keys typed ahead before the mine went off must not close it
+        while poll(Duration::ZERO)? {
+            read()?;
+        }
+        loop {
+            match read()? {
+                Event::Key(ke) if ke.kind != KeyEventKind::Release => return Ok(()),
+                Event::Resize(_, _) => {
+                    self.renderer.invalidate();
+                    self.draw()?;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Move the viewport so the cursor stays inside of it, away from the
+    /// edges when the screen is big enough.
+    fn scroll(&mut self) {
+        let (height, width) = viewport_size();
+        let margin_i = SCROLL_MARGIN.min((height - 1) / 2);
+        let margin_j = SCROLL_MARGIN.min((width - 1) / 2);
+        self.top = self
+            .top
+            .clamp(self.i + margin_i + 1 - height, self.i - margin_i);
+        self.left = self
+            .left
+            .clamp(self.j + margin_j + 1 - width, self.j - margin_j);
+    }
+
+    fn draw(&mut self) -> crossterm::Result<()> {
+        let (height, width) = viewport_size();
+        let (columns, rows) = terminal::size().unwrap_or((80, 24));
+        let glyphs = self.config.glyphs();
+        let theme = self.config.theme();
+        let lost = matches!(self.sweeper.state(), BoardState::Finished(_));
+        let mut frame = Frame::new(columns as usize, rows as usize);
+        let status = format!(
+            "Score: {}  Position: ({}, {})  Mines: {:.0}%",
+            self.sweeper.score(),
+            self.i,
+            self.j,
+            self.sweeper.density() * 100.0,
+        );
+        frame.put_str(0, 0, &status, Style::default());
+        draw_box(
+            &mut frame,
+            glyphs,
+            (0, BOARD_TOP - 1),
+            (BOARD_LEFT + width as usize * 2, BOARD_TOP + height as usize),
+            [None; 4],
+        );
+        for (row, i) in (self.top..self.top + height).enumerate() {
+            for (column, j) in (self.left..self.left + width).enumerate() {
+                let cell = self.sweeper.cell(i, j);
+                let (ch, mut colour) = theme::cell(theme, glyphs, &cell.kind, &cell.state, || {
+                    self.sweeper.count_adjacent_mines(i, j)
+                });
+                let cursor = !lost && (i, j) == (self.i, self.j);
+                if let (true, CellKind::Mine) = (lost, &cell.kind) {
+                    colour = theme.mine();
+                }
+                let style = theme.style(ch, colour, cursor);
+                frame.put(BOARD_LEFT + column * 2, BOARD_TOP + row, ch, style);
+            }
+        }
+        if lost {
+            let text = [
+                "You lost".to_owned(),
+                format!("{} cells cleared", self.sweeper.score()),
+                "press any key".to_owned(),
+            ];
+            let text_width = text.iter().map(String::len).max().unwrap_or(0);
+            let (left, top) = (
+                (columns as usize).saturating_sub(text_width + 4) / 2,
+                (rows as usize).saturating_sub(text.len() + 2) / 2,
+            );
+            for y in top..top + text.len() + 2 {
+                frame.put_str(left, y, &" ".repeat(text_width + 4), Style::default());
+            }
+            draw_box(
+                &mut frame,
+                glyphs,
+                (left, top),
+                (left + text_width + 3, top + text.len() + 1),
+                [None; 4],
+            );
+            for (line, text) in text.iter().enumerate() {
+                frame.put_str(left + 2, top + 1 + line, text, Style::default());
+            }
+        }
+        self.renderer.render(self.w, frame)
+    }
+
+    fn tear_down(&mut self) -> crossterm::Result<()> {
+        disable_raw_mode()?;
+        execute!(self.w, cursor::Show, LeaveAlternateScreen)?;
+        Ok(())
+    }
+}
+
+/// Number of rows and columns of cells that fit on the terminal, leaving
+/// room for the status line and the border.
+fn viewport_size() -> (i64, i64) {
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    let height = (rows as i64 - 3).max(1);
+    let width = ((columns as i64 - 3) / 2).max(1);
+    (height, width)
+}
@@ -1,28 +1,38 @@
+mod accessible;
+mod config;
+mod game;
+mod infinite;
+mod menu;
+mod render;
+mod scores;
+mod stdio;
+mod theme;
+mod viewport;
+
 use std::{
-    io::{stdout, BufWriter, Stdout, Write},
-    ops::{AddAssign, SubAssign},
+    env,
+    io::{stdin, stdout, BufWriter, Stdout},
+    path::Path,
+    time::Duration,
 };
 
 use clap::{Arg, ArgGroup};
-use crossterm::{
-    cursor,
-    event::{
-        read,
-        Event::Key,
-        KeyCode::{Char, Down, Left, Right, Up},
-    },
-    execute, queue,
-    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    QueueableCommand,
-};
 
-use sweeprs::{Board, BoardState, CellKind, CellState, SweeperBoard};
+use sweeprs::{Board, Error, InfiniteBoard, PackedBoard, SweeperBoard, MAX_DENSITY, MIN_DENSITY};
+
+use crate::{
+    accessible::AccessibleGame,
+    config::{Config, Difficulty, Level, Preset},
+    game::{time_seed, Game},
+    infinite::InfiniteGame,
+    stdio::StdioGame,
+    theme::{Glyphs, Theme},
+};
 
 fn main() {
     let matches = clap::App::new("sweeprs")
         .version(env!("CARGO_PKG_VERSION"))
-        .about("A terminal based minesweeper. Use arrow keys to move arround, `a` to open and `s` to flag a cell, `q` to quit the game.")
+        .about("A terminal based minesweeper. By default use arrow keys to move arround, `a` to open and `s` to flag a cell, `?` for a hint, `q` to quit the game. Keys, theme, glyphs and default difficulty can be set in `$XDG_CONFIG_HOME/sweeprs/config.toml`.")
         .author("M. Ilman Nafian <milmannafian04@gmail.com>")
         .arg(
             Arg::with_name("easy")
@@ -59,196 +69,283 @@ fn main() {
                 .display_order(1)
                 .help("Custom board configuration"),
         )
-        .group(ArgGroup::with_name("difficulty").args(&["easy", "medium", "hard", "custom"]))
+        .arg(
+            Arg::with_name("infinite")
+                .short("i")
+                .long("infinite")
+                .takes_value(true)
+                .min_values(0)
+                .value_name("DENSITY")
+                .validator(|arg| match arg.parse::<f64>() {
+                    Ok(density) if (MIN_DENSITY..=MAX_DENSITY).contains(&density) => Ok(()),
+                    _ => Err(format!(
+                        "only accept a density between {} and {}",
+                        MIN_DENSITY, MAX_DENSITY
+                    )),
+                })
+                .display_order(1)
+                .help("Endless board scrolling in every direction, 20% mines unless specified."),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .takes_value(true)
+                .validator(|arg| match arg.parse::<u64>() {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err("only accept positive integer".to_string()),
+                })
+                .help("Seed used to generate the infinite board, or the boards of --stdio and --race"),
+        )
+        .arg(
+            Arg::with_name("compact")
+                .long("compact")
+                .conflicts_with("infinite")
+                .help("Use the bit-packed board, meant for huge custom boards"),
+        )
+        .arg(
+            Arg::with_name("scores")
+                .long("scores")
+                .help("Show the high scores of the chosen difficulty, or every board played so far"),
+        )
+        .arg(
+            Arg::with_name("name")
+                .long("name")
+                .takes_value(true)
+                .help("Player name recorded in the high scores, defaults to the user name"),
+        )
+        .arg(
+            Arg::with_name("autoplay")
+                .long("autoplay")
+                .conflicts_with("infinite")
+                .help("Watch the solver play, showing every cell it picks and why"),
+        )
+        .arg(
+            Arg::with_name("delay")
+                .long("delay")
+                .takes_value(true)
+                .value_name("MS")
+                .requires("autoplay")
+                .validator(|arg| match arg.parse::<u64>() {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err("only accept positive integer".to_string()),
+                })
+                .help("Milliseconds between the steps of the autoplay, 500 unless specified"),
+        )
+        .arg(
+            Arg::with_name("accessible")
+                .long("accessible")
+                .conflicts_with_all(&["infinite", "autoplay"])
+                .help("Play by typing commands like `open c5`, answered in plain sentences for screen readers"),
+        )
+        .arg(
+            Arg::with_name("stdio")
+                .long("stdio")
+                .conflicts_with_all(&["infinite", "autoplay", "accessible"])
+                .help("Read commands like `open 3 4` from stdin and answer in JSON lines, for scripts"),
+        )
+        .arg(
+            Arg::with_name("race")
+                .long("race")
+                .conflicts_with_all(&["infinite", "autoplay", "accessible", "stdio"])
+                .help("Two players race side by side on the same board, the first to clear it wins"),
+        )
+        .arg(
+            Arg::with_name("keys")
+                .long("keys")
+                .takes_value(true)
+                .possible_values(Preset::NAMES)
+                .help("Key binding preset"),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .long("theme")
+                .takes_value(true)
+                .possible_values(Theme::NAMES)
+                .help("Colour theme"),
+        )
+        .arg(
+            Arg::with_name("glyphs")
+                .long("glyphs")
+                .takes_value(true)
+                .possible_values(Glyphs::NAMES)
+                .help("Characters used to draw the board"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Read the configuration from FILE instead of the default location"),
+        )
+        .arg(
+            Arg::with_name("print-config")
+                .long("print-config")
+                .help("Print the effective configuration, command line flags included, and exit"),
+        )
+        .group(ArgGroup::with_name("difficulty").args(&[
+            "easy", "medium", "hard", "custom", "infinite",
+        ]))
         .get_matches();
-    let height;
-    let width;
-    let mine_count;
-    if matches.is_present("medium") {
-        height = 16;
-        width = 16;
-        mine_count = 40;
+    let mut config = match Config::load(matches.value_of("config").map(Path::new)) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("error: {}", e);
+            return;
+        }
+    };
+    if let Some(preset) = matches.value_of("keys").and_then(Preset::from_name) {
+        config.keys.preset = preset;
+    }
+    if let Some(theme) = matches.value_of("theme").and_then(Theme::from_name) {
+        config.theme = Some(theme);
+    }
+    if let Some(glyphs) = matches.value_of("glyphs").and_then(Glyphs::from_name) {
+        config.glyphs = Some(glyphs);
+    }
+    if matches.is_present("easy") {
+        config.difficulty = Difficulty::Easy;
+    } else if matches.is_present("medium") {
+        config.difficulty = Difficulty::Medium;
     } else if matches.is_present("hard") {
-        height = 24;
-        width = 24;
-        mine_count = 99;
-    } else if matches.is_present("custom") {
+        config.difficulty = Difficulty::Hard;
+    }
+    config.resolve();
+    if matches.is_present("print-config") {
+        print!("{}", config.dump());
+        return;
+    }
+    if matches.is_present("infinite") {
+        let density = matches
+            .value_of("infinite")
+            .map_or(0.2, |x| x.parse::<f64>().unwrap());
+        let seed = matches
+            .value_of("seed")
+            .map_or_else(time_seed, |x| x.parse::<u64>().unwrap());
+        match InfiniteBoard::new(density, seed) {
+            Ok(board) => {
+                let mut stdout = BufWriter::new(stdout());
+                InfiniteGame::new(board, &config, &mut stdout).run().ok();
+            }
+            Err(e) => println!("error: {}", e),
+        }
+        return;
+    }
+    let level = if matches.is_present("custom") {
         let args: Vec<usize> = matches
             .values_of("custom")
             .unwrap()
             .map(|x| x.to_string().parse::<usize>().unwrap())
             .collect();
-        height = args[0];
-        width = args[1];
-        mine_count = args[2];
+        Level::Custom {
+            height: args[0],
+            width: args[1],
+            mine_count: args[2],
+        }
     } else {
-        height = 9;
-        width = 9;
-        mine_count = 10;
+        Level::Difficulty(config.difficulty)
     };
-    match Board::new(height, width, mine_count) {
-        Ok(board) => {
-            let mut stdout = BufWriter::new(stdout());
-            Game::new(board, &mut stdout).run().ok();
+    if matches.is_present("scores") {
+        let records = scores::load();
+        if matches.is_present("difficulty") {
+            print!(
+                "{}",
+                scores::format_leaderboard(&records, &level.name(), None)
+            );
+        } else {
+            for config in scores::configs(&records) {
+                println!("{}", scores::format_leaderboard(&records, config, None));
+            }
         }
-        Err(e) => println!("error: {}", e),
+        return;
     }
-}
-
-type W = BufWriter<Stdout>;
-
-struct BoundedIndex {
-    index: usize,
-    max: usize,
-}
-
-impl AddAssign<usize> for BoundedIndex {
-    fn add_assign(&mut self, other: usize) {
-        let new = self.index.saturating_add(other);
-        *self = Self {
-            max: self.max,
-            index: if new < self.max { new } else { self.index },
+    if matches.is_present("stdio") {
+        let seed = matches.value_of("seed").map(|x| x.parse::<u64>().unwrap());
+        let result = if matches.is_present("compact") {
+            play_stdio::<PackedBoard>(level, seed)
+        } else {
+            play_stdio::<Board>(level, seed)
         };
+        if let Err(e) = result {
+            println!("error: {}", e);
+        }
+        return;
     }
-}
-
-impl SubAssign<usize> for BoundedIndex {
-    fn sub_assign(&mut self, other: usize) {
-        *self = Self {
-            max: self.max,
-            index: self.index.saturating_sub(other),
-        };
+    let name = matches
+        .value_of("name")
+        .map(str::to_owned)
+        .or_else(|| env::var("USER").ok())
+        .or_else(|| env::var("USERNAME").ok())
+        .unwrap_or_else(|| "player".to_owned());
+    let autoplay = matches.is_present("autoplay").then(|| {
+        let delay = matches
+            .value_of("delay")
+            .map_or(500, |x| x.parse::<u64>().unwrap());
+        Duration::from_millis(delay)
+    });
+    let race = matches.is_present("race").then(|| {
+        matches
+            .value_of("seed")
+            .map_or_else(time_seed, |x| x.parse::<u64>().unwrap())
+    });
+    // games played by the solver and races are not recorded
+    let player = (autoplay.is_none() && race.is_none()).then_some(name);
+    let result = if matches.is_present("accessible") {
+        if matches.is_present("compact") {
+            play_accessible::<PackedBoard>(level, player)
+        } else {
+            play_accessible::<Board>(level, player)
+        }
+    } else if matches.is_present("compact") {
+        play::<PackedBoard>(level, &config, autoplay, player, race)
+    } else {
+        play::<Board>(level, &config, autoplay, player, race)
+    };
+    if let Err(e) = result {
+        println!("error: {}", e);
     }
 }
 
-pub struct Game<'a> {
-    i: BoundedIndex,
-    j: BoundedIndex,
-    sweeper: Board,
-    w: &'a mut W,
-}
-
-impl<'a> Game<'a> {
-    fn new(sweeper: Board, w: &'a mut W) -> Self {
-        Self {
-            i: BoundedIndex {
-                index: 0,
-                max: sweeper.height(),
-            },
-            j: BoundedIndex {
-                index: 0,
-                max: sweeper.width(),
-            },
-            sweeper,
-            w,
-        }
+fn play<B: SweeperBoard>(
+    level: Level,
+    config: &Config,
+    autoplay: Option<Duration>,
+    player: Option<String>,
+    race: Option<u64>,
+) -> Result<(), Error> {
+    let mut stdout = BufWriter::new(stdout());
+    let mut game = match race {
+        Some(seed) => Game::<B>::race(level, seed, config, &mut stdout)?,
+        None => Game::<B>::new(level, config, &mut stdout)?,
+    };
+    if let Some(delay) = autoplay {
+        game.autoplay(delay);
     }
-
-    fn run(&mut self) -> crossterm::Result<()> {
-        execute!(self.w, EnterAlternateScreen, cursor::Hide)?;
-        enable_raw_mode()?;
-        self.draw()?;
-
-        loop {
-            if let BoardState::Finished(_) = self.sweeper.state() {
-                break;
-            }
-            match read() {
-                Ok(event) => {
-                    if let Key(ke) = event {
-                        match ke.code {
-                            Char('q') => break,
-                            Char('a') => {
-                                self.sweeper.open(self.i.index, self.j.index);
-                            }
-                            Char('s') => {
-                                self.sweeper.flag(self.i.index, self.j.index);
-                            }
-                            Up => self.i -= 1,
-                            Down => self.i += 1,
-                            Left => self.j -= 1,
-                            Right => self.j += 1,
-                            _ => (),
-                        }
-                    }
-                }
-                Err(e) => {
-                    self.tear_down().ok();
-                    panic!("{}", e)
-                }
-            }
-            self.draw()?;
-        }
-
-        self.tear_down()?;
-        match self.sweeper.state() {
-            BoardState::Finished(sweeprs::BoardResult::Win) => println!("You win"),
-            BoardState::Finished(sweeprs::BoardResult::Lost) => println!("You lost"),
-            _ => println!("Game stopped"),
-        }
-        self.draw()?;
-        Ok(())
+    if let Some(name) = player {
+        game.record_as(name);
     }
+    game.run().ok();
+    Ok(())
+}
 
-    fn draw(&mut self) -> crossterm::Result<()> {
-        self.w.queue(Print(format!(
-            "┌{}┐\n\r",
-            "─".repeat(self.sweeper.width() * 2 + 1)
-        )))?;
-        for (i_idx, row) in self.sweeper.cells().iter().enumerate() {
-            self.w.queue(Print("│ "))?;
-            for (j_idx, cell) in row.iter().enumerate() {
-                let cell_char = match cell.state {
-                    CellState::Closed => "█".to_owned(),
-                    CellState::Flagged => "▒".to_owned(),
-                    CellState::Opened => match cell.kind {
-                        CellKind::Uninitialized => "█".to_owned(),
-                        CellKind::Mine => "●".to_owned(),
-                        CellKind::Free => {
-                            let mine_count = self.sweeper.count_adjacent_mines(i_idx, j_idx);
-                            if mine_count == 0 {
-                                " ".to_owned()
-                            } else {
-                                mine_count.to_string()
-                            }
-                        }
-                    },
-                };
-                match self.sweeper.state() {
-                    BoardState::Uninitialized | BoardState::Playing => {
-                        if i_idx == self.i.index && j_idx == self.j.index {
-                            if cell_char == " " {
-                                self.w.queue(SetBackgroundColor(Color::Red))?;
-                            } else {
-                                self.w.queue(SetForegroundColor(Color::Red))?;
-                            }
-                        }
-                        queue!(self.w, Print(cell_char), ResetColor)?;
-                    }
-                    BoardState::Finished(_) => {
-                        if let CellKind::Mine = cell.kind {
-                            self.w.queue(SetForegroundColor(Color::Red))?;
-                        }
-                        queue!(self.w, Print(cell_char), ResetColor)?;
-                    }
-                }
-                if j_idx < row.len() - 1 {
-                    self.w.queue(Print(" "))?;
-                }
-            }
-            self.w.queue(Print(" │\n\r"))?;
-        }
-        self.w.queue(Print(format!(
-            "└{}┘\n\r",
-            "─".repeat(self.sweeper.width() * 2 + 1)
-        )))?;
-        self.w.flush()?;
-        Ok(())
+fn play_accessible<B: SweeperBoard>(level: Level, player: Option<String>) -> Result<(), Error> {
+    let mut game = AccessibleGame::<B>::new(level)?;
+    if let Some(name) = player {
+        game.record_as(name);
     }
+    game.run(stdin().lock(), &mut stdout()).ok();
+    Ok(())
+}
 
-    fn tear_down(&mut self) -> crossterm::Result<()> {
-        disable_raw_mode()?;
-        execute!(self.w, cursor::Show, LeaveAlternateScreen)?;
-        Ok(())
-    }
+fn play_stdio<B: SweeperBoard>(level: Level, seed: Option<u64>) -> Result<(), Error> {
+    let (height, width, mine_count) = level.dimension();
+    let sweeper = match seed {
+        Some(seed) => B::with_seed(height, width, mine_count, seed)?,
+        None => B::new(height, width, mine_count)?,
+    };
+    StdioGame::new(sweeper)
+        .run(stdin().lock(), &mut stdout().lock())
+        .ok();
+    Ok(())
 }
+
+type W = BufWriter<Stdout>;
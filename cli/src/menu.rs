@@ -0,0 +1,222 @@
+use crate::{
+    config::{Difficulty, Level},
+    game::draw_box,
+    render::{Frame, Style},
+    theme::GlyphSet,
+};
+
+/// What picking a menu entry does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Entry {
+    Resume,
+    NewGame,
+    /// Play the same mine layout again.
+    Restart,
+    /// New game on another board.
+    Level(Level),
+    Quit,
+}
+
+/// List of entries drawn in a box over the board, below a few lines of
+/// text such as the result of the game.
+pub struct Menu {
+    text: Vec<String>,
+    entries: Vec<Entry>,
+    current: Level,
+    selected: usize,
+    /// Top left corner of the box when it was last drawn, to map clicks
+    /// back to entries.
+    origin: Option<(usize, usize)>,
+}
+
+impl Menu {
+    /// `Resume` is only offered while a game is going on. Every difficulty
+    /// can be picked, and the current board too when it is custom.
+    pub fn new(text: Vec<String>, current: Level, resume: bool) -> Self {
+        let mut entries = Vec::new();
+        if resume {
+            entries.push(Entry::Resume);
+        }
+        entries.extend([Entry::NewGame, Entry::Restart]);
+        entries.extend(
+            [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+                .map(|difficulty| Entry::Level(Level::Difficulty(difficulty))),
+        );
+        if let Level::Custom { .. } = current {
+            entries.push(Entry::Level(current));
+        }
+        entries.push(Entry::Quit);
+        Self {
+            text,
+            entries,
+            current,
+            selected: 0,
+            origin: None,
+        }
+    }
+
+    pub fn selected(&self) -> Entry {
+        self.entries[self.selected]
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(self.entries.len() - 1);
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1) % self.entries.len();
+    }
+
+    fn label(&self, entry: Entry) -> String {
+        match entry {
+            Entry::Resume => "Resume".to_owned(),
+            Entry::NewGame => "New game".to_owned(),
+            Entry::Restart => "Restart this board".to_owned(),
+            Entry::Level(level) => {
+                let name = match level {
+                    Level::Difficulty(difficulty) => difficulty.name(),
+                    Level::Custom { .. } => "custom",
+                };
+                let (height, width, mine_count) = level.dimension();
+                format!(
+                    "{:<7} {}x{}, {} mines{}",
+                    name,
+                    height,
+                    width,
+                    mine_count,
+                    if level == self.current { " *" } else { "" },
+                )
+            }
+            Entry::Quit => "Quit".to_owned(),
+        }
+    }
+
+    /// Text lines, then a blank line when there is text, then the entries.
+    fn lines(&self) -> Vec<String> {
+        let mut lines = self.text.clone();
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(self.entries.iter().map(|&entry| self.label(entry)));
+        lines
+    }
+
+    /// Width and height of the box, borders included.
+    pub fn size(&self) -> (usize, usize) {
+        let lines = self.lines();
+        let width = lines.iter().map(|line| line.chars().count()).max();
+        (width.unwrap_or(0) + 4, lines.len() + 2)
+    }
+
+    /// Draw the box with its top left corner at `origin`, over whatever is
+    /// in the frame.
+    pub fn draw(&mut self, frame: &mut Frame, glyphs: &GlyphSet, origin: (usize, usize)) {
+        let (width, height) = self.size();
+        let (left, top) = origin;
+        for y in top..top + height {
+            for x in left..left + width {
+                frame.put(x, y, ' ', Style::default());
+            }
+        }
+        draw_box(
+            frame,
+            glyphs,
+            origin,
+            (left + width - 1, top + height - 1),
+            [None; 4],
+        );
+        let first_entry = self.lines().len() - self.entries.len();
+        for (index, line) in self.lines().iter().enumerate() {
+            let style = if index >= first_entry && index - first_entry == self.selected {
+                Style {
+                    reverse: true,
+                    ..Style::default()
+                }
+            } else {
+                Style::default()
+            };
+            let y = top + 1 + index;
+            let line = format!(" {:<1$} ", line, width - 4);
+            frame.put_str(left + 1, y, &line, style);
+        }
+        self.origin = Some(origin);
+    }
+
+    /// Entry drawn at a terminal coordinate, selecting it.
+    pub fn entry_at(&mut self, column: u16, row: u16) -> Option<Entry> {
+        let (left, top) = self.origin?;
+        let (width, _) = self.size();
+        let (column, row) = (column as usize, row as usize);
+        let first_entry = top + 1 + self.lines().len() - self.entries.len();
+        if column <= left || column >= left + width - 1 || row < first_entry {
+            return None;
+        }
+        let index = row - first_entry;
+        if index < self.entries.len() {
+            self.selected = index;
+            Some(self.entries[index])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Glyphs;
+
+    #[test]
+    fn entries() {
+        let easy = Level::Difficulty(Difficulty::Easy);
+        let menu = Menu::new(Vec::new(), easy, true);
+        assert_eq!(menu.selected(), Entry::Resume);
+        assert_eq!(menu.entries.len(), 7);
+        let custom = Level::Custom {
+            height: 30,
+            width: 16,
+            mine_count: 99,
+        };
+        let menu = Menu::new(vec!["You win".to_owned()], custom, false);
+        assert_eq!(menu.selected(), Entry::NewGame);
+        assert!(menu.entries.contains(&Entry::Level(custom)));
+        assert_eq!(
+            menu.label(Entry::Level(custom)),
+            "custom  30x16, 99 mines *"
+        );
+    }
+
+    #[test]
+    fn move_around() {
+        let mut menu = Menu::new(Vec::new(), Level::Difficulty(Difficulty::Easy), false);
+        menu.up();
+        assert_eq!(menu.selected(), Entry::Quit);
+        menu.down();
+        menu.down();
+        assert_eq!(menu.selected(), Entry::Restart);
+    }
+
+    #[test]
+    fn click_on_entry() {
+        let mut menu = Menu::new(
+            vec!["You lost".to_owned()],
+            Level::Difficulty(Difficulty::Easy),
+            false,
+        );
+        assert_eq!(menu.entry_at(5, 5), None);
+        let (width, height) = menu.size();
+        let mut frame = Frame::new(80, 24);
+        menu.draw(&mut frame, Glyphs::Ascii.set(), (10, 2));
+        // border, text, blank line, then the entries
+        assert_eq!(menu.entry_at(12, 3), None);
+        assert_eq!(menu.entry_at(12, 5), Some(Entry::NewGame));
+        assert_eq!(menu.entry_at(12, 6), Some(Entry::Restart));
+        assert_eq!(menu.selected(), Entry::Restart);
+        assert_eq!(menu.entry_at(10, 6), None);
+        assert_eq!(menu.entry_at(12, 2 + height as u16 - 2), Some(Entry::Quit));
+        assert_eq!(menu.entry_at(10 + width as u16, 6), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::io::Write;
+
+use crossterm::{
+    cursor, queue,
+    style::{
+        Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+    },
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+/// Colours and attributes of one character on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub reverse: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    pub fn fg(colour: Option<Color>) -> Self {
+        Self {
+            fg: colour,
+            ..Self::default()
+        }
+    }
+
+    fn queue(self, w: &mut impl Write) -> crossterm::Result<()> {
+        queue!(w, SetAttribute(Attribute::Reset), ResetColor)?;
+        if let Some(fg) = self.fg {
+            w.queue(SetForegroundColor(fg))?;
+        }
+        if let Some(bg) = self.bg {
+            w.queue(SetBackgroundColor(bg))?;
+        }
+        if self.reverse {
+            w.queue(SetAttribute(Attribute::Reverse))?;
+        }
+        if self.underline {
+            w.queue(SetAttribute(Attribute::Underlined))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cell {
+    ch: char,
+    style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+/// Grid of characters for one screen, drawn in memory and then handed to
+/// a `Renderer`. Anything written outside of it is dropped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+        }
+    }
+
+    pub fn put(&mut self, x: usize, y: usize, ch: char, style: Style) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = Cell { ch, style };
+        }
+    }
+
+    /// Write `text` from `x` onwards and return the column after it.
+    pub fn put_str(&mut self, x: usize, y: usize, text: &str, style: Style) -> usize {
+        text.chars().fold(x, |x, ch| {
+            self.put(x, y, ch, style);
+            x + 1
+        })
+    }
+}
+
+/// Keeps the frame on screen and only sends what changed in the next one.
+#[derive(Default)]
+pub struct Renderer {
+    previous: Option<Frame>,
+}
+
+impl Renderer {
+    /// Forget what is on screen, the next frame is drawn from scratch.
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+    }
+
+    pub fn render(&mut self, w: &mut impl Write, frame: Frame) -> crossterm::Result<()> {
+        let previous = self
+            .previous
+            .take()
+            .filter(|previous| (previous.width, previous.height) == (frame.width, frame.height));
+        if previous.is_none() {
+            queue!(w, ResetColor, Clear(ClearType::All))?;
+        }
+        let mut style = None;
+        // position the terminal cursor would be at after the last print
+        let mut at = None;
+        for (index, cell) in frame.cells.iter().enumerate() {
+            let unchanged = match &previous {
+                Some(previous) => previous.cells[index] == *cell,
+                None => *cell == Cell::default(),
+            };
+            if unchanged {
+                continue;
+            }
+            let (x, y) = (index % frame.width, index / frame.width);
+            if at != Some((x, y)) {
+                w.queue(cursor::MoveTo(x as u16, y as u16))?;
+            }
+            if style != Some(cell.style) {
+                cell.style.queue(w)?;
+                style = Some(cell.style);
+            }
+            w.queue(Print(cell.ch))?;
+            at = Some((x + 1, y));
+        }
+        if style.is_some() {
+            queue!(w, SetAttribute(Attribute::Reset), ResetColor)?;
+        }
+        w.flush()?;
+        self.previous = Some(frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn put_outside() {
+        let mut frame = Frame::new(3, 2);
+        assert_eq!(frame.put_str(1, 1, "abc", Style::default()), 4);
+        frame.put(0, 5, 'x', Style::default());
+        let chars: String = frame.cells.iter().map(|cell| cell.ch).collect();
+        assert_eq!(chars, "    ab");
+    }
+
+    #[test]
+    fn only_changes_are_sent() {
+        let mut renderer = Renderer::default();
+        let mut frame = Frame::new(40, 10);
+        frame.put_str(0, 0, "status", Style::default());
+        let mut first = Vec::new();
+        renderer.render(&mut first, frame.clone()).unwrap();
+        let first = String::from_utf8(first).unwrap();
+        assert!(first.contains("status"));
+
+        frame.put(5, 5, '1', Style::fg(Some(Color::Blue)));
+        let mut second = Vec::new();
+        renderer.render(&mut second, frame.clone()).unwrap();
+        let second = String::from_utf8(second).unwrap();
+        assert!(!second.contains("status"));
+        assert!(second.contains("\x1b[6;6H"));
+        assert!(second.contains('1'));
+
+        let mut third = Vec::new();
+        renderer.render(&mut third, frame.clone()).unwrap();
+        assert!(third.is_empty());
+
+        renderer.invalidate();
+        let mut fourth = Vec::new();
+        renderer.render(&mut fourth, frame).unwrap();
+        assert!(String::from_utf8(fourth).unwrap().contains("status"));
+    }
+}
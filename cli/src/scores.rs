@@ -0,0 +1,279 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Number of entries shown per board configuration.
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// A finished game, one line of the score file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    /// Board configuration, e.g. `easy` or `custom 30x16 99`.
+    pub config: String,
+    pub won: bool,
+    pub time: Duration,
+    pub three_bv: usize,
+    /// Unix timestamp of the end of the game.
+    pub date: u64,
+    /// Number of hints used, assisted games never reach the leaderboard.
+    pub hints: usize,
+    pub name: String,
+}
+
+impl Record {
+    /// Record of a game finished just now. The time is cut to the
+    /// millisecond like in the file, so the record can be found back in the
+    /// leaderboard.
+    pub fn finished(
+        config: String,
+        won: bool,
+        time: Duration,
+        three_bv: usize,
+        hints: usize,
+        name: String,
+    ) -> Self {
+        Self {
+            config,
+            won,
+            time: Duration::from_millis(time.as_millis() as u64),
+            three_bv,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            hints,
+            name,
+        }
+    }
+
+    /// Tab separated, the name goes last since it is free text.
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.config,
+            if self.won { "win" } else { "lost" },
+            self.time.as_millis(),
+            self.three_bv,
+            self.date,
+            self.hints,
+            self.name.replace(['\t', '\n', '\r'], " "),
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(7, '\t');
+        Some(Self {
+            config: fields.next()?.to_owned(),
+            won: match fields.next()? {
+                "win" => true,
+                "lost" => false,
+                _ => return None,
+            },
+            time: Duration::from_millis(fields.next()?.parse().ok()?),
+            three_bv: fields.next()?.parse().ok()?,
+            date: fields.next()?.parse().ok()?,
+            hints: fields.next()?.parse().ok()?,
+            name: fields.next()?.to_owned(),
+        })
+    }
+
+    pub fn is_assisted(&self) -> bool {
+        self.hints > 0
+    }
+}
+
+/// `$XDG_DATA_HOME/sweeprs/scores.tsv`, falling back to
+/// `~/.local/share/sweeprs/scores.tsv`.
+pub fn path() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(data_home.join("sweeprs").join("scores.tsv"))
+}
+
+/// Read every record. A missing or unreadable file is treated as empty
+/// and corrupted lines are skipped, so a broken file never stops a game.
+pub fn load() -> Vec<Record> {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map_or_else(Vec::new, |content| parse(&content))
+}
+
+fn parse(content: &str) -> Vec<Record> {
+    content.lines().filter_map(Record::from_line).collect()
+}
+
+pub fn save(record: &Record) -> io::Result<()> {
+    let path = path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data dir"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record.to_line())
+}
+
+/// Save `record` and tell where it ranks in the high scores, or why it
+/// could not be saved. `None` when it is not ranked.
+pub fn save_and_rank(record: &Record) -> Option<String> {
+    if let Err(e) = save(record) {
+        return Some(format!("Could not save the score: {}", e));
+    }
+    let records = load();
+    let rank = leaderboard(&records, &record.config)
+        .iter()
+        .position(|&saved| saved == record)?;
+    Some(format!(
+        "#{} in the {} high scores",
+        rank + 1,
+        record.config
+    ))
+}
+
+/// Save `record` and, after a win, show the high scores of its
+/// configuration with it marked, or why it could not be saved.
+pub fn save_and_show(record: &Record) -> Vec<String> {
+    if let Err(e) = save(record) {
+        return vec![format!("Could not save the score: {}", e)];
+    }
+    if !record.won {
+        return Vec::new();
+    }
+    format_leaderboard(&load(), &record.config, Some(record))
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Fastest unassisted wins for the configuration.
+pub fn leaderboard<'a>(records: &'a [Record], config: &str) -> Vec<&'a Record> {
+    let mut wins: Vec<&Record> = records
+        .iter()
+        .filter(|record| record.config == config && record.won && !record.is_assisted())
+        .collect();
+    wins.sort_by_key(|record| record.time);
+    wins.truncate(LEADERBOARD_SIZE);
+    wins
+}
+
+/// Render the leaderboard as a plain text table, marking `highlight`.
+pub fn format_leaderboard(records: &[Record], config: &str, highlight: Option<&Record>) -> String {
+    let mut table = format!("High scores ({})\n", config);
+    let wins = leaderboard(records, config);
+    if wins.is_empty() {
+        table.push_str("  no games won yet\n");
+        return table;
+    }
+    table.push_str("  #  name              time     3BV  3BV/s  date\n");
+    for (rank, record) in wins.iter().enumerate() {
+        let seconds = record.time.as_secs_f64();
+        table.push_str(&format!(
+            "{:>3}  {:<16} {:>7.3} {:>5} {:>6.2}  {}{}\n",
+            rank + 1,
+            record.name,
+            seconds,
+            record.three_bv,
+            record.three_bv as f64 / seconds.max(0.001),
+            format_date(record.date),
+            if Some(*record) == highlight {
+                "  <"
+            } else {
+                ""
+            },
+        ));
+    }
+    table
+}
+
+/// Every configuration that has at least one record, in order of first
+/// appearance.
+pub fn configs(records: &[Record]) -> Vec<&str> {
+    let mut configs: Vec<&str> = Vec::new();
+    for record in records {
+        if !configs.contains(&record.config.as_str()) {
+            configs.push(&record.config);
+        }
+    }
+    configs
+}
+
+/// Format a unix timestamp as `YYYY-MM-DD` in UTC.
+fn format_date(timestamp: u64) -> String {
+    // days to civil date, from Howard Hinnant's date algorithms
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(config: &str, millis: u64, hints: usize) -> Record {
+        Record {
+            config: config.to_owned(),
+            won: true,
+            time: Duration::from_millis(millis),
+            three_bv: 20,
+            date: 1_600_000_000,
+            hints,
+            name: "player".to_owned(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let record = record("custom 30x16 99", 12_345, 0);
+        assert_eq!(Record::from_line(&record.to_line()), Some(record));
+    }
+
+    #[test]
+    fn skip_corrupted_lines() {
+        let content = format!(
+            "garbage\neasy\twin\tnot a number\t1\t1\t0\tname\n{}\n\n",
+            record("easy", 1_000, 0).to_line()
+        );
+        assert_eq!(parse(&content), vec![record("easy", 1_000, 0)]);
+    }
+
+    #[test]
+    fn leaderboard_order() {
+        let mut lost = record("easy", 1, 0);
+        lost.won = false;
+        let records = vec![
+            record("easy", 3_000, 0),
+            record("easy", 1_000, 0),
+            record("medium", 500, 0),
+            record("easy", 200, 1),
+            lost,
+            record("easy", 2_000, 0),
+        ];
+        let times: Vec<u128> = leaderboard(&records, "easy")
+            .iter()
+            .map(|record| record.time.as_millis())
+            .collect();
+        assert_eq!(times, [1_000, 2_000, 3_000]);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_600_000_000), "2020-09-13");
+    }
+}
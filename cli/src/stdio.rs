@@ -0,0 +1,264 @@
+use std::io::{self, BufRead, Write};
+
+use serde::Serialize;
+
+use sweeprs::{BoardResult, BoardState, CellKind, CellState, Pos, SweeperBoard, SweeperCell};
+
+/// Game driven by one command per line, answered with one JSON object per
+/// line, for scripts and end-to-end tests.
+///
+/// Cells are addressed by row then column, counted from 0. Every reply
+/// holds `ok`, and either `error` or the fields of the command.
+pub struct StdioGame<B: SweeperBoard> {
+    sweeper: B,
+}
+
+#[derive(Serialize)]
+struct Reply {
+    ok: bool,
+    #[serde(flatten)]
+    body: Body,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Body {
+    Error {
+        error: String,
+    },
+    /// Cells opened by `open` or `chord`, in board order.
+    Open {
+        state: State,
+        opened: Vec<Opened>,
+    },
+    Flag {
+        state: State,
+        flagged: bool,
+        mines_left: isize,
+    },
+    State {
+        state: State,
+        height: usize,
+        width: usize,
+        mines: usize,
+        mines_left: isize,
+        three_bv: usize,
+    },
+    /// One string per row, see `symbol`.
+    Board {
+        state: State,
+        rows: Vec<String>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum State {
+    /// No cell opened yet, the mines are not placed.
+    New,
+    Playing,
+    Won,
+    Lost,
+}
+
+#[derive(Serialize)]
+struct Opened {
+    row: usize,
+    column: usize,
+    mine: bool,
+    adjacent: usize,
+}
+
+impl<B: SweeperBoard> StdioGame<B> {
+    pub fn new(sweeper: B) -> Self {
+        Self { sweeper }
+    }
+
+    /// Reply to every line of `input` until it ends.
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match self.execute(&line) {
+                Ok(body) => Reply { ok: true, body },
+                Err(error) => Reply {
+                    ok: false,
+                    body: Body::Error { error },
+                },
+            };
+            serde_json::to_writer(&mut *output, &reply)?;
+            writeln!(output)?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, line: &str) -> Result<Body, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["open", i, j] => {
+                let pos = self.pos(i, j)?;
+                self.playing()?;
+                match self.sweeper.cell(pos).state() {
+                    CellState::Closed => Ok(self.open(pos)),
+                    CellState::Flagged => Err("cell is flagged".to_owned()),
+                    CellState::Opened => Err("cell is already open".to_owned()),
+                }
+            }
+            ["chord", i, j] => {
+                let pos = self.pos(i, j)?;
+                self.playing()?;
+                match self.sweeper.cell(pos).state() {
+                    CellState::Opened => Ok(self.open(pos)),
+                    _ => Err("cell is not open".to_owned()),
+                }
+            }
+            ["flag", i, j] => {
+                let pos = self.pos(i, j)?;
+                self.playing()?;
+                let flagged = match self.sweeper.flag(pos) {
+                    CellState::Opened => return Err("cell is open".to_owned()),
+                    state => matches!(state, CellState::Flagged),
+                };
+                Ok(Body::Flag {
+                    state: self.state(),
+                    flagged,
+                    mines_left: self.sweeper.remaining_flags(),
+                })
+            }
+            ["state"] => Ok(Body::State {
+                state: self.state(),
+                height: self.sweeper.height(),
+                width: self.sweeper.width(),
+                mines: self.sweeper.mine_count(),
+                mines_left: self.sweeper.remaining_flags(),
+                three_bv: self.sweeper.three_bv(),
+            }),
+            ["board"] => Ok(Body::Board {
+                state: self.state(),
+                rows: self.rows(),
+            }),
+            _ => Err(format!("unknown command `{}`", line.trim())),
+        }
+    }
+
+    fn pos(&self, i: &str, j: &str) -> Result<Pos, String> {
+        let (i, j) = match (i.parse::<usize>(), j.parse::<usize>()) {
+            (Ok(i), Ok(j)) => (i, j),
+            _ => return Err("row and column must be positive integers".to_owned()),
+        };
+        if i < self.sweeper.height() && j < self.sweeper.width() {
+            Ok(Pos::new(i, j))
+        } else {
+            Err(format!("({}, {}) is outside of the board", i, j))
+        }
+    }
+
+    fn playing(&self) -> Result<(), String> {
+        match self.sweeper.state() {
+            BoardState::Finished(_) => Err("game is over".to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    fn state(&self) -> State {
+        match self.sweeper.state() {
+            BoardState::Uninitialized => State::New,
+            BoardState::Playing => State::Playing,
+            BoardState::Finished(BoardResult::Win) => State::Won,
+            BoardState::Finished(BoardResult::Lost) => State::Lost,
+        }
+    }
+
+    /// Open or chord `pos`, telling which cells got opened.
+    fn open(&mut self, pos: Pos) -> Body {
+        let closed: Vec<bool> = self
+            .sweeper
+            .iter()
+            .map(|(_, cell)| !matches!(cell.state(), CellState::Opened))
+            .collect();
+        self.sweeper.open(pos);
+        let opened = self
+            .sweeper
+            .iter()
+            .zip(closed)
+            .filter(|((_, cell), closed)| *closed && matches!(cell.state(), CellState::Opened))
+            .map(|((pos, cell), _)| Opened {
+                row: pos.i,
+                column: pos.j,
+                mine: matches!(cell.kind(), CellKind::Mine),
+                adjacent: self.sweeper.count_adjacent_mines(pos),
+            })
+            .collect();
+        Body::Open {
+            state: self.state(),
+            opened,
+        }
+    }
+
+    fn rows(&self) -> Vec<String> {
+        let finished = matches!(self.sweeper.state(), BoardState::Finished(_));
+        (0..self.sweeper.height())
+            .map(|i| {
+                (0..self.sweeper.width())
+                    .map(|j| symbol(&self.sweeper, Pos::new(i, j), finished))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// `#` closed, `F` flagged, `.` empty, `1` to `8` numbers and `*` mines,
+/// which are only shown once the game is over.
+fn symbol<B: SweeperBoard>(board: &B, pos: Pos, finished: bool) -> char {
+    let cell = board.cell(pos);
+    match (cell.state(), cell.kind()) {
+        (CellState::Flagged, _) => 'F',
+        (CellState::Opened, CellKind::Mine) => '*',
+        (CellState::Closed, CellKind::Mine) if finished => '*',
+        (CellState::Closed, _) => '#',
+        (CellState::Opened, _) => match board.count_adjacent_mines(pos) {
+            0 => '.',
+            mine_count => char::from_digit(mine_count as u32, 10).unwrap_or('?'),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sweeprs::Board;
+
+    use super::*;
+
+    fn replies(input: &str) -> Vec<serde_json::Value> {
+        let mut game = StdioGame::new(Board::with_seed(9, 9, 10, 1).unwrap());
+        let mut output = Vec::new();
+        game.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn commands() {
+        let replies = replies("state\nopen 4 4\n\nflag 0 0\nchord 0 0\nopen 9 0\nboard\njump\n");
+        assert_eq!(replies.len(), 7);
+        assert_eq!(replies[0]["state"], "new");
+        assert_eq!(replies[0]["mines"], 10);
+        assert_eq!(replies[1]["ok"], true);
+        assert_eq!(replies[1]["opened"][0]["adjacent"], 0);
+        assert!(replies[1]["opened"].as_array().unwrap().len() >= 9);
+        // the opening reaches the corner on this seed
+        assert_eq!(replies[2]["error"], "cell is open");
+        assert_eq!(replies[3]["opened"].as_array().unwrap().len(), 0);
+        assert_eq!(replies[4]["error"], "(9, 0) is outside of the board");
+        let rows = replies[5]["rows"].as_array().unwrap();
+        assert_eq!(rows.len(), 9);
+        assert_eq!(rows[4].as_str().unwrap().chars().nth(4), Some('.'));
+        assert_eq!(replies[6]["error"], "unknown command `jump`");
+    }
+}
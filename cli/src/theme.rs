@@ -0,0 +1,371 @@
+use std::env;
+
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+use sweeprs::{CellKind, CellState};
+
+use crate::render::Style;
+
+/// Colours used to draw the board.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Number colours of the original game.
+    Classic,
+    /// Bright colours only, for dim displays and low vision.
+    HighContrast,
+    /// Okabe-Ito palette, numbers stay distinct under common colour
+    /// vision deficiencies.
+    ColourBlind,
+    /// No colour at all, the cursor is shown in reverse video.
+    Monochrome,
+}
+
+impl Theme {
+    pub const NAMES: &'static [&'static str] =
+        &["classic", "high-contrast", "colour-blind", "monochrome"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(Self::Classic),
+            "high-contrast" => Some(Self::HighContrast),
+            "colour-blind" => Some(Self::ColourBlind),
+            "monochrome" => Some(Self::Monochrome),
+            _ => None,
+        }
+    }
+
+    /// Monochrome when `NO_COLOR` is set or the terminal is dumb, classic
+    /// otherwise.
+    pub fn detect() -> Self {
+        Self::detect_with(|name| env::var(name).ok())
+    }
+
+    fn detect_with(var: impl Fn(&str) -> Option<String>) -> Self {
+        let no_color = var("NO_COLOR").is_some_and(|value| !value.is_empty());
+        if no_color || is_dumb(&var) {
+            Self::Monochrome
+        } else {
+            Self::Classic
+        }
+    }
+
+    /// Style of a character in `colour`, the cursor takes the theme colour
+    /// instead, as the background on blanks so it stays visible, or
+    /// reverse video when the theme has no colours.
+    pub fn style(self, ch: char, colour: Option<Color>, cursor: bool) -> Style {
+        match (cursor, self.cursor()) {
+            (true, Some(cursor)) if ch == ' ' => Style {
+                bg: Some(cursor),
+                ..Style::default()
+            },
+            (true, Some(cursor)) => Style::fg(Some(cursor)),
+            (true, None) => Style {
+                reverse: true,
+                ..Style::default()
+            },
+            (false, _) => Style::fg(colour),
+        }
+    }
+
+    /// Colour of the cell under the cursor, `None` means reverse video.
+    pub fn cursor(self) -> Option<Color> {
+        match self {
+            Self::Classic => Some(Color::Red),
+            Self::HighContrast => Some(Color::Yellow),
+            Self::ColourBlind => Some(Color::Rgb {
+                r: 230,
+                g: 159,
+                b: 0,
+            }),
+            Self::Monochrome => None,
+        }
+    }
+
+    /// Background of the cell suggested by a hint.
+    pub fn hint(self) -> Option<Color> {
+        match self {
+            Self::Classic => Some(Color::DarkGreen),
+            Self::HighContrast => Some(Color::Blue),
+            Self::ColourBlind => Some(Color::Rgb {
+                r: 0,
+                g: 114,
+                b: 178,
+            }),
+            Self::Monochrome => None,
+        }
+    }
+
+    /// Mark `style` as the hinted cell, underlined when the theme has no
+    /// colours.
+    pub fn highlight(self, style: Style) -> Style {
+        match self.hint() {
+            Some(hint) => Style {
+                bg: Some(hint),
+                ..style
+            },
+            None => Style {
+                underline: true,
+                ..style
+            },
+        }
+    }
+
+    /// Colour of the mines revealed at the end of the game.
+    pub fn mine(self) -> Option<Color> {
+        match self {
+            Self::Classic => Some(Color::Red),
+            Self::HighContrast => Some(Color::Red),
+            Self::ColourBlind => Some(Color::Rgb {
+                r: 213,
+                g: 94,
+                b: 0,
+            }),
+            Self::Monochrome => None,
+        }
+    }
+
+    pub fn flag(self) -> Option<Color> {
+        match self {
+            Self::Classic => None,
+            Self::HighContrast => Some(Color::Yellow),
+            Self::ColourBlind => Some(Color::Rgb {
+                r: 240,
+                g: 228,
+                b: 66,
+            }),
+            Self::Monochrome => None,
+        }
+    }
+
+    /// Colour of a number, `mine_count` goes from 1 to 8.
+    pub fn number(self, mine_count: usize) -> Option<Color> {
+        let colours = match self {
+            // the classic black 7 would disappear on a dark terminal
+            Self::Classic => [
+                Color::Blue,
+                Color::DarkGreen,
+                Color::Red,
+                Color::DarkBlue,
+                Color::DarkRed,
+                Color::DarkCyan,
+                Color::DarkMagenta,
+                Color::Grey,
+            ],
+            Self::HighContrast => [
+                Color::Cyan,
+                Color::Green,
+                Color::Red,
+                Color::Magenta,
+                Color::Yellow,
+                Color::Blue,
+                Color::White,
+                Color::White,
+            ],
+            Self::ColourBlind => [
+                Color::Rgb {
+                    r: 0,
+                    g: 114,
+                    b: 178,
+                },
+                Color::Rgb {
+                    r: 0,
+                    g: 158,
+                    b: 115,
+                },
+                Color::Rgb {
+                    r: 213,
+                    g: 94,
+                    b: 0,
+                },
+                Color::Rgb {
+                    r: 204,
+                    g: 121,
+                    b: 167,
+                },
+                Color::Rgb {
+                    r: 230,
+                    g: 159,
+                    b: 0,
+                },
+                Color::Rgb {
+                    r: 86,
+                    g: 180,
+                    b: 233,
+                },
+                Color::Rgb {
+                    r: 240,
+                    g: 228,
+                    b: 66,
+                },
+                Color::Grey,
+            ],
+            Self::Monochrome => return None,
+        };
+        colours.get(mine_count.checked_sub(1)?).copied()
+    }
+}
+
+/// Characters used to draw the board.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Glyphs {
+    Unicode,
+    /// Plain ASCII for terminals or fonts without box drawing characters.
+    Ascii,
+}
+
+pub struct GlyphSet {
+    pub closed: char,
+    pub flagged: char,
+    pub mine: char,
+    pub horizontal: char,
+    pub vertical: char,
+    /// Top left, top right, bottom left and bottom right.
+    pub corners: [char; 4],
+    /// Up, down, left and right scroll indicators.
+    pub arrows: [char; 4],
+}
+
+const UNICODE: GlyphSet = GlyphSet {
+    closed: '█',
+    flagged: '▒',
+    mine: '●',
+    horizontal: '─',
+    vertical: '│',
+    corners: ['┌', '┐', '└', '┘'],
+    arrows: ['▲', '▼', '◀', '▶'],
+};
+
+const ASCII: GlyphSet = GlyphSet {
+    closed: '#',
+    flagged: 'F',
+    mine: '*',
+    horizontal: '-',
+    vertical: '|',
+    corners: ['+', '+', '+', '+'],
+    arrows: ['^', 'v', '<', '>'],
+};
+
+impl Glyphs {
+    pub const NAMES: &'static [&'static str] = &["unicode", "ascii"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unicode" => Some(Self::Unicode),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+
+    /// ASCII when the terminal is dumb or, on unix, the locale is not
+    /// UTF-8, unicode otherwise.
+    pub fn detect() -> Self {
+        Self::detect_with(|name| env::var(name).ok())
+    }
+
+    fn detect_with(var: impl Fn(&str) -> Option<String>) -> Self {
+        if is_dumb(&var) {
+            return Self::Ascii;
+        }
+        if cfg!(unix) {
+            // the first locale variable set wins, like in setlocale
+            let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+                .iter()
+                .find_map(|name| var(name).filter(|value| !value.is_empty()));
+            let utf8 = locale.is_some_and(|locale| {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            });
+            if !utf8 {
+                return Self::Ascii;
+            }
+        }
+        Self::Unicode
+    }
+
+    pub fn set(self) -> &'static GlyphSet {
+        match self {
+            Self::Unicode => &UNICODE,
+            Self::Ascii => &ASCII,
+        }
+    }
+}
+
+fn is_dumb(var: impl Fn(&str) -> Option<String>) -> bool {
+    var("TERM").is_some_and(|term| term == "dumb")
+}
+
+/// Character and colour of a cell. `mine_count` is only called for
+/// opened free cells.
+pub fn cell(
+    theme: Theme,
+    glyphs: &GlyphSet,
+    kind: &CellKind,
+    state: &CellState,
+    mine_count: impl FnOnce() -> usize,
+) -> (char, Option<Color>) {
+    match state {
+        CellState::Closed => (glyphs.closed, None),
+        CellState::Flagged => (glyphs.flagged, theme.flag()),
+        CellState::Opened => match kind {
+            CellKind::Uninitialized => (glyphs.closed, None),
+            CellKind::Mine => (glyphs.mine, theme.mine()),
+            CellKind::Free => match mine_count() {
+                0 => (' ', None),
+                mine_count => (
+                    char::from_digit(mine_count as u32, 10).unwrap_or('?'),
+                    theme.number(mine_count),
+                ),
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn detect_theme() {
+        let vars = [("TERM", "xterm-256color")];
+        assert_eq!(Theme::detect_with(env(&vars)), Theme::Classic);
+        let vars = [("TERM", "xterm-256color"), ("NO_COLOR", "1")];
+        assert_eq!(Theme::detect_with(env(&vars)), Theme::Monochrome);
+        let vars = [("TERM", "xterm-256color"), ("NO_COLOR", "")];
+        assert_eq!(Theme::detect_with(env(&vars)), Theme::Classic);
+        let vars = [("TERM", "dumb")];
+        assert_eq!(Theme::detect_with(env(&vars)), Theme::Monochrome);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detect_glyphs() {
+        let vars = [("TERM", "xterm"), ("LANG", "en_US.UTF-8")];
+        assert_eq!(Glyphs::detect_with(env(&vars)), Glyphs::Unicode);
+        let vars = [("TERM", "xterm"), ("LC_ALL", "C"), ("LANG", "en_US.UTF-8")];
+        assert_eq!(Glyphs::detect_with(env(&vars)), Glyphs::Ascii);
+        let vars = [("TERM", "dumb"), ("LANG", "en_US.utf8")];
+        assert_eq!(Glyphs::detect_with(env(&vars)), Glyphs::Ascii);
+        assert_eq!(Glyphs::detect_with(env(&[])), Glyphs::Ascii);
+    }
+
+    #[test]
+    fn number_colours() {
+        for theme in [Theme::Classic, Theme::HighContrast, Theme::ColourBlind] {
+            assert_eq!(theme.number(0), None);
+            assert!((1..=8).all(|count| theme.number(count).is_some()));
+            assert_eq!(theme.number(9), None);
+        }
+        assert!((0..=9).all(|count| Theme::Monochrome.number(count).is_none()));
+    }
+}
@@ -0,0 +1,209 @@
+use sweeprs::Pos;
+
+/// Screen row of the first board row, below the status line and the top
+/// border.
+pub const BOARD_TOP: usize = 2;
+
+/// Screen column of the first board column, after the left border.
+pub const BOARD_LEFT: usize = 2;
+
+/// Cells kept between the cursor and the edge of the viewport before it
+/// starts following the cursor.
+const SCROLL_MARGIN: usize = 3;
+
+/// Largest minimap, in characters.
+const MINIMAP_WIDTH: usize = 16;
+const MINIMAP_HEIGHT: usize = 8;
+
+/// Fewest board columns left on screen when making room for the minimap.
+const MIN_COLUMNS: usize = 8;
+
+/// Scaled down board shown next to a board that does not fit on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Minimap {
+    pub rows: usize,
+    pub columns: usize,
+    /// Board rows and columns covered by one minimap character.
+    pub block_rows: usize,
+    pub block_columns: usize,
+}
+
+impl Minimap {
+    fn new(height: usize, width: usize, max_rows: usize, max_columns: usize) -> Self {
+        let block_rows = height.div_ceil(max_rows.min(height));
+        let block_columns = width.div_ceil(max_columns.min(width));
+        Self {
+            rows: height.div_ceil(block_rows),
+            columns: width.div_ceil(block_columns),
+            block_rows,
+            block_columns,
+        }
+    }
+}
+
+/// Part of the board visible on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Viewport {
+    /// Board coordinate of the top left visible cell.
+    pub top: usize,
+    pub left: usize,
+    /// Number of board rows and columns visible at once.
+    pub rows: usize,
+    pub columns: usize,
+    pub minimap: Option<Minimap>,
+}
+
+impl Viewport {
+    /// The whole board, whatever the size of the screen.
+    #[cfg(test)]
+    pub fn full(height: usize, width: usize) -> Self {
+        Self {
+            top: 0,
+            left: 0,
+            rows: height,
+            columns: width,
+            minimap: None,
+        }
+    }
+
+    /// Resize the viewport to what fits on a screen of `screen_columns` by
+    /// `screen_rows`, keeping the line under the board free for hints. The
+    /// minimap is only shown when the board does not fit and there is room
+    /// left for it.
+    pub fn fit(&mut self, screen_columns: u16, screen_rows: u16, height: usize, width: usize) {
+        // the bottom border and the hint line
+        let rows = (screen_rows as usize).saturating_sub(BOARD_TOP + 2).max(1);
+        // two columns per cell, plus the borders and their padding
+        let columns = (screen_columns as usize).saturating_sub(3) / 2;
+        self.minimap = None;
+        if height > rows || width > columns {
+            let minimap_rows = MINIMAP_HEIGHT.min(rows - 1);
+            // the minimap box and a space to separate it from the board
+            let reserved = (MINIMAP_WIDTH + 3).div_ceil(2);
+            if minimap_rows >= 2 && columns >= MIN_COLUMNS + reserved {
+                self.minimap = Some(Minimap::new(height, width, minimap_rows, MINIMAP_WIDTH));
+                self.rows = height.min(rows);
+                self.columns = width.min(columns - reserved);
+                return self.clamp(height, width);
+            }
+        }
+        self.rows = height.min(rows);
+        self.columns = width.min(columns.max(1));
+        self.clamp(height, width);
+    }
+
+    /// Scroll so `pos` is visible, away from the edges when the viewport
+    /// is big enough.
+    pub fn follow(&mut self, pos: Pos, height: usize, width: usize) {
+        let margin = SCROLL_MARGIN.min(self.rows.saturating_sub(1) / 2);
+        self.top = self.top.clamp(
+            (pos.i + margin + 1).saturating_sub(self.rows),
+            pos.i.saturating_sub(margin),
+        );
+        let margin = SCROLL_MARGIN.min(self.columns.saturating_sub(1) / 2);
+        self.left = self.left.clamp(
+            (pos.j + margin + 1).saturating_sub(self.columns),
+            pos.j.saturating_sub(margin),
+        );
+        self.clamp(height, width);
+    }
+
+    fn clamp(&mut self, height: usize, width: usize) {
+        self.top = self.top.min(height.saturating_sub(self.rows));
+        self.left = self.left.min(width.saturating_sub(self.columns));
+    }
+
+    /// Map a terminal coordinate back to the board cell drawn there. Every
+    /// cell takes two columns, the character and a space, after the
+    /// border. Clicking on the space picks the cell on its left.
+    pub fn cell_at(&self, column: u16, row: u16) -> Option<Pos> {
+        let (column, row) = (column as usize, row as usize);
+        if row < BOARD_TOP || column < BOARD_LEFT {
+            return None;
+        }
+        let (i, j) = (row - BOARD_TOP, (column - BOARD_LEFT) / 2);
+        if i < self.rows && j < self.columns {
+            Some(Pos::new(self.top + i, self.left + j))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visible(view: &Viewport, pos: Pos) -> bool {
+        (view.top..view.top + view.rows).contains(&pos.i)
+            && (view.left..view.left + view.columns).contains(&pos.j)
+    }
+
+    #[test]
+    fn click_on_cells() {
+        let view = Viewport::full(9, 9);
+        assert_eq!(view.cell_at(2, 2), Some(Pos::new(0, 0)));
+        assert_eq!(view.cell_at(3, 2), Some(Pos::new(0, 0)));
+        assert_eq!(view.cell_at(4, 3), Some(Pos::new(1, 1)));
+        assert_eq!(view.cell_at(18, 10), Some(Pos::new(8, 8)));
+    }
+
+    #[test]
+    fn click_outside_board() {
+        let view = Viewport::full(9, 9);
+        assert_eq!(view.cell_at(2, 0), None);
+        assert_eq!(view.cell_at(2, 1), None);
+        assert_eq!(view.cell_at(1, 2), None);
+        assert_eq!(view.cell_at(20, 2), None);
+        assert_eq!(view.cell_at(2, 11), None);
+    }
+
+    #[test]
+    fn click_scrolled() {
+        let mut view = Viewport::default();
+        view.fit(80, 24, 100, 100);
+        view.follow(Pos::new(50, 60), 100, 100);
+        assert!(visible(&view, Pos::new(50, 60)));
+        assert_eq!(view.cell_at(2, 2), Some(Pos::new(view.top, view.left)));
+    }
+
+    #[test]
+    fn fit_small_board() {
+        let mut view = Viewport::default();
+        view.fit(80, 24, 9, 9);
+        assert_eq!(view, Viewport::full(9, 9));
+    }
+
+    #[test]
+    fn fit_large_board() {
+        let mut view = Viewport::default();
+        view.fit(80, 24, 100, 200);
+        assert_eq!((view.rows, view.columns), (20, 28));
+        // the hint line is the last one of the screen
+        assert_eq!(BOARD_TOP + view.rows + 1, 23);
+        let minimap = view.minimap.unwrap();
+        assert!(minimap.rows <= MINIMAP_HEIGHT && minimap.columns <= MINIMAP_WIDTH);
+        assert!(minimap.rows * minimap.block_rows >= 100);
+        assert!(minimap.columns * minimap.block_columns >= 200);
+        // too narrow for the minimap
+        view.fit(30, 24, 100, 200);
+        assert_eq!((view.rows, view.columns, view.minimap), (20, 13, None));
+    }
+
+    #[test]
+    fn follow_cursor() {
+        let mut view = Viewport::default();
+        view.fit(80, 24, 100, 100);
+        view.follow(Pos::new(30, 0), 100, 100);
+        assert_eq!(view.top, 30 + SCROLL_MARGIN + 1 - view.rows);
+        view.follow(Pos::new(99, 99), 100, 100);
+        assert_eq!((view.top, view.left), (100 - view.rows, 100 - view.columns));
+        view.follow(Pos::new(0, 0), 100, 100);
+        assert_eq!((view.top, view.left), (0, 0));
+        // shrinking the screen keeps the cursor inside after following
+        view.follow(Pos::new(60, 60), 100, 100);
+        view.fit(20, 8, 100, 100);
+        view.follow(Pos::new(60, 60), 100, 100);
+        assert!(visible(&view, Pos::new(60, 60)));
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use serde_json::Value;
+
+/// Run the binary in `--stdio` mode on a seeded easy board, feeding it
+/// `input` and parsing every line it answers.
+fn run(args: &[&str], input: &str) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sweeprs"))
+        .args(["--stdio", "--easy"])
+        .args(args)
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn same_seed_same_game() {
+    let input = "open 4 4\nboard\n";
+    let first = run(&["--seed", "3"], input);
+    assert_eq!(first, run(&["--seed", "3"], input));
+    assert_eq!(first, run(&["--seed", "3", "--compact"], input));
+    assert_eq!(first[1]["rows"].as_array().unwrap().len(), 9);
+}
+
+#[test]
+fn open_a_mine() {
+    let replies = run(&["--seed", "5"], "open 4 4\nboard\n");
+    // opening every closed cell in order hits a mine sooner or later, every
+    // move after it is refused
+    let mut input = "open 4 4\n".to_owned();
+    for (i, row) in replies[1]["rows"].as_array().unwrap().iter().enumerate() {
+        for (j, symbol) in row.as_str().unwrap().chars().enumerate() {
+            if symbol == '#' {
+                input.push_str(&format!("open {} {}\n", i, j));
+            }
+        }
+    }
+    input.push_str("state\n");
+    let replies = run(&["--seed", "5"], &input);
+    let lost = replies
+        .iter()
+        .position(|reply| reply["state"] == "lost")
+        .unwrap();
+    let opened = replies[lost]["opened"].as_array().unwrap();
+    assert_eq!(opened.len(), 1);
+    assert_eq!(opened[0]["mine"], true);
+    let (after, state) = replies[lost + 1..].split_at(replies.len() - lost - 2);
+    assert!(after.iter().all(|reply| reply["error"] == "game is over"));
+    assert_eq!(state[0]["state"], "lost");
+}
+
+#[test]
+fn errors() {
+    let replies = run(&[], "open a b\nflag 0 9\nchord 4 4\njump\n");
+    assert!(replies.iter().all(|reply| reply["ok"] == false));
+    assert_eq!(replies[2]["error"], "cell is not open");
+}
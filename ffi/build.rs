@@ -0,0 +1,23 @@
+use std::{env, path::Path};
+
+/// Generate the C header in `OUT_DIR`, where `tests/c.rs` checks that the
+/// committed `include/sweeprs.h` matches it. With `SWEEPRS_UPDATE_HEADER`
+/// set the committed header is rewritten, the source tree is left alone
+/// otherwise.
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let crate_dir = Path::new(&crate_dir);
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/lib.rs"))
+        .generate()
+        .expect("cannot generate the C header");
+    bindings.write_to_file(Path::new(&env::var("OUT_DIR").unwrap()).join("sweeprs.h"));
+    if env::var_os("SWEEPRS_UPDATE_HEADER").is_some() {
+        bindings.write_to_file(crate_dir.join("include/sweeprs.h"));
+    }
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=SWEEPRS_UPDATE_HEADER");
+}
@@ -0,0 +1,162 @@
+#ifndef SWEEPRS_H
+#define SWEEPRS_H
+
+/* Generated by cbindgen from src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Returned by every function, `SWEEPRS_ERROR_OK` when it worked.
+ */
+typedef enum SweeprsError {
+  SWEEPRS_ERROR_OK = 0,
+  /**
+   * The board configuration is invalid, like more mines than cells.
+   */
+  SWEEPRS_ERROR_INVALID_CONFIG,
+  /**
+   * The cell is outside of the board.
+   */
+  SWEEPRS_ERROR_INDEX_OUT_OF_BOUND,
+  /**
+   * A pointer given was null.
+   */
+  SWEEPRS_ERROR_NULL_POINTER,
+} SweeprsError;
+
+typedef enum SweeprsState {
+  /**
+   * No cell opened yet, the mines are not placed.
+   */
+  SWEEPRS_STATE_NEW,
+  SWEEPRS_STATE_PLAYING,
+  SWEEPRS_STATE_WON,
+  SWEEPRS_STATE_LOST,
+} SweeprsState;
+
+typedef enum SweeprsCellState {
+  SWEEPRS_CELL_STATE_CLOSED,
+  SWEEPRS_CELL_STATE_FLAGGED,
+  SWEEPRS_CELL_STATE_OPENED,
+} SweeprsCellState;
+
+/**
+ * A game of minesweeper, only handled through pointers.
+ */
+typedef struct SweeprsBoard SweeprsBoard;
+
+typedef struct SweeprsStatus {
+  enum SweeprsState state;
+  size_t height;
+  size_t width;
+  size_t mines;
+  /**
+   * Mines left to flag, negative when there are more flags than mines.
+   */
+  ptrdiff_t mines_left;
+} SweeprsStatus;
+
+/**
+ * Everything the engine knows about a cell, hide `mine` from the player
+ * until the cell is open or the game is over.
+ */
+typedef struct SweeprsCell {
+  enum SweeprsCellState state;
+  bool mine;
+  size_t adjacent_mines;
+} SweeprsCell;
+
+/**
+ * Make a board and point `board` to it, or to null when the configuration
+ * is invalid. Its mines are placed at random.
+ *
+ * # Safety
+ *
+ * `board` must be null or valid for writes.
+ */
+enum SweeprsError sweeprs_board_new(size_t height,
+                                    size_t width,
+                                    size_t mines,
+                                    struct SweeprsBoard **board);
+
+/**
+ * Same as `sweeprs_board_new`, but the mines are placed from `seed`, so
+ * the same seed and first move always give the same board.
+ *
+ * # Safety
+ *
+ * `board` must be null or valid for writes.
+ */
+enum SweeprsError sweeprs_board_new_seeded(size_t height,
+                                           size_t width,
+                                           size_t mines,
+                                           uint64_t seed,
+                                           struct SweeprsBoard **board);
+
+/**
+ * Free a board, doing nothing on null.
+ *
+ * # Safety
+ *
+ * `board` must be null or made by `sweeprs_board_new` or
+ * `sweeprs_board_new_seeded`, and not used afterwards.
+ */
+void sweeprs_board_free(struct SweeprsBoard *board);
+
+/**
+ * Open a closed cell. Nothing happens on other cells or once the game is
+ * over, like every move.
+ *
+ * # Safety
+ *
+ * `board` must be null or a live board.
+ */
+enum SweeprsError sweeprs_board_open(struct SweeprsBoard *board, size_t row, size_t column);
+
+/**
+ * Flag a closed cell or remove the flag of a flagged one.
+ *
+ * # Safety
+ *
+ * `board` must be null or a live board.
+ */
+enum SweeprsError sweeprs_board_flag(struct SweeprsBoard *board, size_t row, size_t column);
+
+/**
+ * Open around an open number whose mines are all flagged.
+ *
+ * # Safety
+ *
+ * `board` must be null or a live board.
+ */
+enum SweeprsError sweeprs_board_chord(struct SweeprsBoard *board, size_t row, size_t column);
+
+/**
+ * Write the state, size and mines of the board to `status`.
+ *
+ * # Safety
+ *
+ * `board` must be null or a live board, `status` null or valid for
+ * writes.
+ */
+enum SweeprsError sweeprs_board_status(const struct SweeprsBoard *board,
+                                       struct SweeprsStatus *status);
+
+/**
+ * Write the cell at `row` and `column` to `cell`. No cell has a mine
+ * before the first one is opened.
+ *
+ * # Safety
+ *
+ * `board` must be null or a live board, `cell` null or valid for writes.
+ */
+enum SweeprsError sweeprs_board_cell(const struct SweeprsBoard *board,
+                                     size_t row,
+                                     size_t column,
+                                     struct SweeprsCell *cell);
+
+#endif  /* SWEEPRS_H */
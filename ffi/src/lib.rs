@@ -0,0 +1,267 @@
+//! C bindings of the sweeprs engine, declared in `include/sweeprs.h`.
+//!
+//! Boards are opaque handles made by `sweeprs_board_new` and freed by
+//! `sweeprs_board_free`. Every other function returns a `SweeprsError` and
+//! writes what it answers through a pointer. Cells are addressed by row
+//! then column, counted from 0.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use sweeprs::{
+    Board, BoardResult, BoardState, CellKind, CellState, Error, Move, Pos, Refused, SweeperBoard,
+    SweeperCell,
+};
+
+/// A game of minesweeper, only handled through pointers.
+pub struct SweeprsBoard {
+    board: Board,
+}
+
+/// Returned by every function, `SWEEPRS_ERROR_OK` when it worked.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweeprsError {
+    Ok = 0,
+    /// The board configuration is invalid, like more mines than cells.
+    InvalidConfig,
+    /// The cell is outside of the board.
+    IndexOutOfBound,
+    /// A pointer given was null.
+    NullPointer,
+}
+
+impl From<Error> for SweeprsError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::InvalidConfigError => Self::InvalidConfig,
+            Error::IndexOutOfBoundError => Self::IndexOutOfBound,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweeprsState {
+    /// No cell opened yet, the mines are not placed.
+    New,
+    Playing,
+    Won,
+    Lost,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweeprsCellState {
+    Closed,
+    Flagged,
+    Opened,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SweeprsStatus {
+    pub state: SweeprsState,
+    pub height: usize,
+    pub width: usize,
+    pub mines: usize,
+    /// Mines left to flag, negative when there are more flags than mines.
+    pub mines_left: isize,
+}
+
+/// Everything the engine knows about a cell, hide `mine` from the player
+/// until the cell is open or the game is over.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SweeprsCell {
+    pub state: SweeprsCellState,
+    pub mine: bool,
+    pub adjacent_mines: usize,
+}
+
+/// Make a board and point `board` to it, or to null when the configuration
+/// is invalid. Its mines are placed at random.
+///
+/// # Safety
+///
+/// `board` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_new(
+    height: usize,
+    width: usize,
+    mines: usize,
+    board: *mut *mut SweeprsBoard,
+) -> SweeprsError {
+    create(board, || Board::new(height, width, mines))
+}
+
+/// Same as `sweeprs_board_new`, but the mines are placed from `seed`, so
+/// the same seed and first move always give the same board.
+///
+/// # Safety
+///
+/// `board` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_new_seeded(
+    height: usize,
+    width: usize,
+    mines: usize,
+    seed: u64,
+    board: *mut *mut SweeprsBoard,
+) -> SweeprsError {
+    create(board, || Board::with_seed(height, width, mines, seed))
+}
+
+unsafe fn create(
+    out: *mut *mut SweeprsBoard,
+    new: impl FnOnce() -> Result<Board, Error>,
+) -> SweeprsError {
+    if out.is_null() {
+        return SweeprsError::NullPointer;
+    }
+    *out = ptr::null_mut();
+    // a panic must not unwind into C, a board that cannot be made is an
+    // invalid configuration whatever the reason
+    match panic::catch_unwind(AssertUnwindSafe(new)) {
+        Ok(Ok(board)) => {
+            *out = Box::into_raw(Box::new(SweeprsBoard { board }));
+            SweeprsError::Ok
+        }
+        Ok(Err(e)) => e.into(),
+        Err(_) => SweeprsError::InvalidConfig,
+    }
+}
+
+/// Free a board, doing nothing on null.
+///
+/// # Safety
+///
+/// `board` must be null or made by `sweeprs_board_new` or
+/// `sweeprs_board_new_seeded`, and not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_free(board: *mut SweeprsBoard) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// Open a closed cell. Nothing happens on other cells or once the game is
+/// over, like every move.
+///
+/// # Safety
+///
+/// `board` must be null or a live board.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_open(
+    board: *mut SweeprsBoard,
+    row: usize,
+    column: usize,
+) -> SweeprsError {
+    play(board, row, column, Move::Open)
+}
+
+/// Flag a closed cell or remove the flag of a flagged one.
+///
+/// # Safety
+///
+/// `board` must be null or a live board.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_flag(
+    board: *mut SweeprsBoard,
+    row: usize,
+    column: usize,
+) -> SweeprsError {
+    play(board, row, column, Move::Flag)
+}
+
+/// Open around an open number whose mines are all flagged.
+///
+/// # Safety
+///
+/// `board` must be null or a live board.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_chord(
+    board: *mut SweeprsBoard,
+    row: usize,
+    column: usize,
+) -> SweeprsError {
+    play(board, row, column, Move::Chord)
+}
+
+/// A move refused by the rules is ignored, only a cell outside of the
+/// board is an error.
+unsafe fn play(board: *mut SweeprsBoard, row: usize, column: usize, action: Move) -> SweeprsError {
+    let board = match board.as_mut() {
+        Some(board) => &mut board.board,
+        None => return SweeprsError::NullPointer,
+    };
+    match board.play(action, Pos::new(row, column)) {
+        Err(Refused::OutOfBound) => Error::IndexOutOfBoundError.into(),
+        Ok(()) | Err(_) => SweeprsError::Ok,
+    }
+}
+
+/// Write the state, size and mines of the board to `status`.
+///
+/// # Safety
+///
+/// `board` must be null or a live board, `status` null or valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_status(
+    board: *const SweeprsBoard,
+    status: *mut SweeprsStatus,
+) -> SweeprsError {
+    let (board, status) = match (board.as_ref(), status.as_mut()) {
+        (Some(board), Some(status)) => (&board.board, status),
+        _ => return SweeprsError::NullPointer,
+    };
+    *status = SweeprsStatus {
+        state: match board.state() {
+            BoardState::Uninitialized => SweeprsState::New,
+            BoardState::Playing => SweeprsState::Playing,
+            BoardState::Finished(BoardResult::Win) => SweeprsState::Won,
+            BoardState::Finished(BoardResult::Lost) => SweeprsState::Lost,
+        },
+        height: board.height(),
+        width: board.width(),
+        mines: board.mine_count(),
+        mines_left: board.remaining_flags(),
+    };
+    SweeprsError::Ok
+}
+
+/// Write the cell at `row` and `column` to `cell`. No cell has a mine
+/// before the first one is opened.
+///
+/// # Safety
+///
+/// `board` must be null or a live board, `cell` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sweeprs_board_cell(
+    board: *const SweeprsBoard,
+    row: usize,
+    column: usize,
+    cell: *mut SweeprsCell,
+) -> SweeprsError {
+    let (board, out) = match (board.as_ref(), cell.as_mut()) {
+        (Some(board), Some(cell)) => (&board.board, cell),
+        _ => return SweeprsError::NullPointer,
+    };
+    if row >= board.height() || column >= board.width() {
+        return Error::IndexOutOfBoundError.into();
+    }
+    let cell = board.cell(Pos::new(row, column));
+    *out = SweeprsCell {
+        state: match cell.state() {
+            CellState::Closed => SweeprsCellState::Closed,
+            CellState::Flagged => SweeprsCellState::Flagged,
+            CellState::Opened => SweeprsCellState::Opened,
+        },
+        mine: matches!(cell.kind(), CellKind::Mine),
+        adjacent_mines: board.count_adjacent_mines(Pos::new(row, column)),
+    };
+    SweeprsError::Ok
+}
@@ -0,0 +1,103 @@
+/* Plays through the C header, built and run by tests/c.rs. */
+
+#include <stdint.h>
+#include <stdio.h>
+
+#include "sweeprs.h"
+
+#define CHECK(condition)                                                   \
+  do {                                                                     \
+    if (!(condition)) {                                                    \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,     \
+              #condition);                                                 \
+      return 1;                                                            \
+    }                                                                      \
+  } while (0)
+
+static int errors(void) {
+  SweeprsBoard *board = (SweeprsBoard *)1;
+  CHECK(sweeprs_board_new(9, 9, 81, &board) == SWEEPRS_ERROR_INVALID_CONFIG);
+  CHECK(board == NULL);
+  CHECK(sweeprs_board_new(SIZE_MAX, 9, 0, &board) ==
+        SWEEPRS_ERROR_INVALID_CONFIG);
+  CHECK(sweeprs_board_new(SIZE_MAX / 9, 9, 0, &board) ==
+        SWEEPRS_ERROR_INVALID_CONFIG);
+  CHECK(sweeprs_board_new(9, 9, 10, NULL) == SWEEPRS_ERROR_NULL_POINTER);
+  CHECK(sweeprs_board_open(NULL, 0, 0) == SWEEPRS_ERROR_NULL_POINTER);
+  sweeprs_board_free(NULL);
+
+  CHECK(sweeprs_board_new(9, 9, 10, &board) == SWEEPRS_ERROR_OK);
+  CHECK(sweeprs_board_open(board, 9, 0) == SWEEPRS_ERROR_INDEX_OUT_OF_BOUND);
+  CHECK(sweeprs_board_flag(board, 0, 9) == SWEEPRS_ERROR_INDEX_OUT_OF_BOUND);
+  SweeprsCell cell;
+  CHECK(sweeprs_board_cell(board, 9, 9, &cell) ==
+        SWEEPRS_ERROR_INDEX_OUT_OF_BOUND);
+  CHECK(sweeprs_board_status(board, NULL) == SWEEPRS_ERROR_NULL_POINTER);
+  sweeprs_board_free(board);
+  return 0;
+}
+
+static int play(void) {
+  SweeprsBoard *board;
+  SweeprsStatus status;
+  SweeprsCell cell;
+  CHECK(sweeprs_board_new_seeded(9, 9, 10, 5, &board) == SWEEPRS_ERROR_OK);
+  CHECK(sweeprs_board_status(board, &status) == SWEEPRS_ERROR_OK);
+  CHECK(status.state == SWEEPRS_STATE_NEW);
+  CHECK(status.height == 9 && status.width == 9 && status.mines == 10);
+
+  CHECK(sweeprs_board_open(board, 4, 4) == SWEEPRS_ERROR_OK);
+  CHECK(sweeprs_board_cell(board, 4, 4, &cell) == SWEEPRS_ERROR_OK);
+  CHECK(cell.state == SWEEPRS_CELL_STATE_OPENED);
+  CHECK(!cell.mine && cell.adjacent_mines == 0);
+
+  /* flag the first closed cell, a flagged cell cannot be opened */
+  size_t row = 0, column = 0;
+  for (;;) {
+    CHECK(sweeprs_board_cell(board, row, column, &cell) == SWEEPRS_ERROR_OK);
+    if (cell.state == SWEEPRS_CELL_STATE_CLOSED) break;
+    if (++column == 9) column = 0, row++;
+  }
+  CHECK(sweeprs_board_flag(board, row, column) == SWEEPRS_ERROR_OK);
+  CHECK(sweeprs_board_open(board, row, column) == SWEEPRS_ERROR_OK);
+  CHECK(sweeprs_board_cell(board, row, column, &cell) == SWEEPRS_ERROR_OK);
+  CHECK(cell.state == SWEEPRS_CELL_STATE_FLAGGED);
+  CHECK(sweeprs_board_status(board, &status) == SWEEPRS_ERROR_OK);
+  CHECK(status.state == SWEEPRS_STATE_PLAYING && status.mines_left == 9);
+
+  /* opening every other closed cell hits a mine sooner or later */
+  for (size_t i = 0; i < 9; i++) {
+    for (size_t j = 0; j < 9; j++) {
+      CHECK(sweeprs_board_open(board, i, j) == SWEEPRS_ERROR_OK);
+    }
+  }
+  CHECK(sweeprs_board_status(board, &status) == SWEEPRS_ERROR_OK);
+  CHECK(status.state == SWEEPRS_STATE_LOST);
+  sweeprs_board_free(board);
+  return 0;
+}
+
+static int seeded(void) {
+  SweeprsBoard *first, *second;
+  SweeprsCell a, b;
+  CHECK(sweeprs_board_new_seeded(16, 16, 40, 7, &first) == SWEEPRS_ERROR_OK);
+  CHECK(sweeprs_board_new_seeded(16, 16, 40, 7, &second) == SWEEPRS_ERROR_OK);
+  sweeprs_board_open(first, 8, 8);
+  sweeprs_board_open(second, 8, 8);
+  for (size_t i = 0; i < 16; i++) {
+    for (size_t j = 0; j < 16; j++) {
+      sweeprs_board_cell(first, i, j, &a);
+      sweeprs_board_cell(second, i, j, &b);
+      CHECK(a.mine == b.mine && a.state == b.state);
+    }
+  }
+  sweeprs_board_free(first);
+  sweeprs_board_free(second);
+  return 0;
+}
+
+int main(void) {
+  if (errors() || play() || seeded()) return 1;
+  puts("ok");
+  return 0;
+}
@@ -0,0 +1,42 @@
+use std::{env, fs, path::Path, process::Command};
+
+/// The committed header is the one cbindgen makes from the sources.
+#[test]
+fn header_is_up_to_date() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let committed = fs::read_to_string(manifest_dir.join("include/sweeprs.h")).unwrap();
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/sweeprs.h"));
+    assert!(
+        committed == generated,
+        "include/sweeprs.h is out of date, run `SWEEPRS_UPDATE_HEADER=1 cargo build -p sweeprs-ffi`"
+    );
+}
+
+/// Build `tests/board.c` against the static library and the committed
+/// header, then run it.
+#[test]
+fn c_program() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // cargo builds the library next to the test, in target/<profile>/deps
+    let exe = env::current_exe().unwrap();
+    let library = exe.parent().unwrap().join("libsweeprs_ffi.a");
+    let program = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sweeprs-ffi-board");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(compiler)
+        .args(["-std=c99", "-Wall", "-Werror", "-I"])
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/board.c"))
+        .arg(library)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, b"ok\n");
+}
@@ -0,0 +1,317 @@
+//! Python bindings of the sweeprs engine, imported as `sweeprs` once built
+//! with `maturin`.
+//!
+//! ```python
+//! import numpy as np
+//! import sweeprs
+//!
+//! board = sweeprs.Board(9, 9, 10, seed=3)
+//! observation, state, played = board.step("open", 4, 4)
+//! cells = np.asarray(observation)  # uint8, shape (9, 9)
+//! print(state, (cells == sweeprs.CLOSED).sum())
+//! ```
+
+use std::{
+    ffi::{c_int, c_void},
+    ptr,
+};
+
+use pyo3::{
+    exceptions::{PyBufferError, PyIndexError, PyValueError},
+    ffi,
+    prelude::*,
+    types::PyList,
+};
+
+use sweeprs::{BoardResult, BoardState, CellView, Error, Move, Pos, Refused, SweeperBoard};
+
+fn py_error(e: Error) -> PyErr {
+    match e {
+        Error::InvalidConfigError => PyValueError::new_err(e.to_string()),
+        Error::IndexOutOfBoundError => PyIndexError::new_err(e.to_string()),
+    }
+}
+
+/// A game of minesweeper. Cells are addressed by row then column, counted
+/// from 0.
+#[pyclass(module = "sweeprs")]
+pub struct Board {
+    board: sweeprs::Board,
+}
+
+#[pymethods]
+impl Board {
+    /// Raise `ValueError` when the configuration is invalid. The same seed
+    /// and first move always give the same board.
+    #[new]
+    #[pyo3(signature = (height = 9, width = 9, mines = 10, seed = None))]
+    fn new(height: usize, width: usize, mines: usize, seed: Option<u64>) -> PyResult<Self> {
+        let board = match seed {
+            Some(seed) => sweeprs::Board::with_seed(height, width, mines, seed),
+            None => sweeprs::Board::new(height, width, mines),
+        };
+        Ok(Self {
+            board: board.map_err(py_error)?,
+        })
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.board.height()
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.board.width()
+    }
+
+    #[getter]
+    fn mines(&self) -> usize {
+        self.board.mine_count()
+    }
+
+    /// Mines left to flag, negative when there are more flags than mines.
+    #[getter]
+    fn mines_left(&self) -> isize {
+        self.board.remaining_flags()
+    }
+
+    /// `"new"` before the first cell is opened, then `"playing"`, `"won"`
+    /// or `"lost"`.
+    #[getter]
+    fn state(&self) -> &'static str {
+        match self.board.state() {
+            BoardState::Uninitialized => "new",
+            BoardState::Playing => "playing",
+            BoardState::Finished(BoardResult::Win) => "won",
+            BoardState::Finished(BoardResult::Lost) => "lost",
+        }
+    }
+
+    /// Open a closed cell. Returns whether the move was played, it is not
+    /// on other cells or once the game is over. Raises `IndexError` when
+    /// the cell is outside of the board, like every move.
+    fn open(&mut self, row: usize, column: usize) -> PyResult<bool> {
+        self.play(Move::Open, row, column)
+    }
+
+    /// Flag a closed cell or remove the flag of a flagged one.
+    fn flag(&mut self, row: usize, column: usize) -> PyResult<bool> {
+        self.play(Move::Flag, row, column)
+    }
+
+    /// Open around an open number whose mines are all flagged.
+    fn chord(&mut self, row: usize, column: usize) -> PyResult<bool> {
+        self.play(Move::Chord, row, column)
+    }
+
+    /// Play `action`, one of `"open"`, `"flag"` or `"chord"`, and return
+    /// the observation after it, the state and whether it was played.
+    fn step(
+        &mut self,
+        action: &str,
+        row: usize,
+        column: usize,
+    ) -> PyResult<(Observation, &'static str, bool)> {
+        let action = match action {
+            "open" => Move::Open,
+            "flag" => Move::Flag,
+            "chord" => Move::Chord,
+            _ => return Err(PyValueError::new_err("action must be open, flag or chord")),
+        };
+        let played = self.play(action, row, column)?;
+        Ok((self.observation(), self.state(), played))
+    }
+
+    /// The board as its player sees it, see `Observation`.
+    fn observation(&self) -> Observation {
+        let cells = self
+            .board
+            .iter()
+            .map(|(pos, _)| self.board.view(pos).code())
+            .collect();
+        let (height, width) = (self.board.height(), self.board.width());
+        Observation {
+            cells,
+            shape: [height as ffi::Py_ssize_t, width as ffi::Py_ssize_t],
+            strides: [width as ffi::Py_ssize_t, 1],
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Board(height={}, width={}, mines={}, state='{}')",
+            self.height(),
+            self.width(),
+            self.mines(),
+            self.state()
+        )
+    }
+}
+
+impl Board {
+    /// A move refused by the rules is not played, only a cell outside of
+    /// the board raises.
+    fn play(&mut self, action: Move, row: usize, column: usize) -> PyResult<bool> {
+        match self.board.play(action, Pos::new(row, column)) {
+            Ok(()) => Ok(true),
+            Err(Refused::OutOfBound) => Err(py_error(Error::IndexOutOfBoundError)),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+/// A read only snapshot of a board, one byte per cell: `0` to `8` for
+/// open cells, `CLOSED`, `FLAGGED`, or `MINE` once the game is over, as
+/// given by `sweeprs::CellView::code`.
+/// Shared through the buffer protocol as a `height` by `width` array, so
+/// `numpy.asarray` and `memoryview` read it without a copy.
+#[pyclass(module = "sweeprs", frozen)]
+pub struct Observation {
+    cells: Vec<u8>,
+    shape: [ffi::Py_ssize_t; 2],
+    strides: [ffi::Py_ssize_t; 2],
+}
+
+#[pymethods]
+impl Observation {
+    /// The cells never change and outlive the buffer, which holds a
+    /// reference to `slf`.
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("view is null"));
+        }
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("observations are read only"));
+        }
+        let observation = slf.get();
+        let requested = |flag| flags & flag == flag;
+        // SAFETY: `view` is not null and Python gives it to be filled
+        unsafe {
+            (*view).buf = observation.cells.as_ptr() as *mut c_void;
+            (*view).len = observation.cells.len() as ffi::Py_ssize_t;
+            (*view).readonly = 1;
+            (*view).itemsize = 1;
+            (*view).format = if requested(ffi::PyBUF_FORMAT) {
+                c"B".as_ptr() as *mut _
+            } else {
+                ptr::null_mut()
+            };
+            (*view).ndim = 2;
+            (*view).shape = if requested(ffi::PyBUF_ND) {
+                observation.shape.as_ptr() as *mut _
+            } else {
+                (*view).ndim = 1;
+                ptr::null_mut()
+            };
+            (*view).strides = if requested(ffi::PyBUF_STRIDES) {
+                observation.strides.as_ptr() as *mut _
+            } else {
+                ptr::null_mut()
+            };
+            (*view).suboffsets = ptr::null_mut();
+            (*view).internal = ptr::null_mut();
+            (*view).obj = slf.into_any().into_ptr();
+        }
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// The cells row by row, as nested lists of ints.
+    fn tolist<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let rows = self
+            .cells
+            .chunks(self.shape[1] as usize)
+            .map(|row| PyList::new(py, row))
+            .collect::<PyResult<Vec<_>>>()?;
+        PyList::new(py, rows)
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "sweeprs")]
+fn sweeprs_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Board>()?;
+    module.add_class::<Observation>()?;
+    module.add("CLOSED", CellView::CLOSED)?;
+    module.add("FLAGGED", CellView::FLAGGED)?;
+    module.add("MINE", CellView::MINE)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use pyo3::{types::IntoPyDict, wrap_pymodule};
+
+    use super::*;
+
+    /// Run `code` with the module imported as `sweeprs`, failing on any
+    /// exception.
+    fn run(code: &CStr) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = wrap_pymodule!(sweeprs_py)(py);
+            let locals = [("sweeprs", module)].into_py_dict(py).unwrap();
+            if let Err(e) = py.run(code, None, Some(&locals)) {
+                e.print(py);
+                panic!("python code failed");
+            }
+        });
+    }
+
+    #[test]
+    fn play() {
+        run(c"
+board = sweeprs.Board(seed=3)
+assert board.state == 'new' and board.mines_left == 10
+assert board.open(4, 4) and not board.open(4, 4)
+observation, state, played = board.step('flag', 0, 0)
+assert state == 'playing'
+assert repr(board) == \"Board(height=9, width=9, mines=10, state='playing')\"
+assert played == (observation.tolist()[0][0] == sweeprs.FLAGGED)
+try:
+    board.step('jump', 0, 0)
+    assert False
+except ValueError:
+    pass
+try:
+    board.flag(9, 0)
+    assert False
+except IndexError:
+    pass
+try:
+    sweeprs.Board(9, 9, 81)
+    assert False
+except ValueError:
+    pass
+");
+    }
+
+    #[test]
+    fn buffer() {
+        run(c"
+board = sweeprs.Board(16, 30, 99, seed=7)
+closed = memoryview(board.observation())
+assert closed.readonly and closed.format == 'B'
+assert closed.shape == (16, 30) and closed.strides == (30, 1)
+assert closed.tolist() == [[sweeprs.CLOSED] * 30] * 16
+board.open(8, 8)
+opened = memoryview(board.observation())
+assert opened[8, 8] == 0 and closed[8, 8] == sweeprs.CLOSED
+assert bytes(opened) == bytes(sum(board.observation().tolist(), []))
+assert len(board.observation()) == 16 * 30
+again = sweeprs.Board(16, 30, 99, seed=7)
+again.open(8, 8)
+assert bytes(memoryview(again.observation())) == bytes(opened)
+");
+    }
+}
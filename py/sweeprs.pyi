@@ -0,0 +1,31 @@
+from typing import Literal, Optional, Tuple
+
+State = Literal["new", "playing", "won", "lost"]
+Action = Literal["open", "flag", "chord"]
+
+CLOSED: int
+FLAGGED: int
+MINE: int
+
+class Observation:
+    """Read only `height` by `width` uint8 buffer: 0 to 8 for open cells,
+    CLOSED, FLAGGED, or MINE once the game is over."""
+
+    def __len__(self) -> int: ...
+    def tolist(self) -> list[list[int]]: ...
+
+class Board:
+    height: int
+    width: int
+    mines: int
+    mines_left: int
+    state: State
+
+    def __init__(
+        self, height: int = 9, width: int = 9, mines: int = 10, seed: Optional[int] = None
+    ) -> None: ...
+    def open(self, row: int, column: int) -> bool: ...
+    def flag(self, row: int, column: int) -> bool: ...
+    def chord(self, row: int, column: int) -> bool: ...
+    def step(self, action: Action, row: int, column: int) -> Tuple[Observation, State, bool]: ...
+    def observation(self) -> Observation: ...
@@ -0,0 +1,135 @@
+//! Multiplayer minesweeper over WebSocket. Clients connect to `/ws`, then
+//! create or join a room and send their moves, the server checks every
+//! move against its own boards and tells each client what they may see.
+//! Single player games are also served over HTTP under `/games`, kept in a
+//! `GameStore`. The messages and routes are described in `PROTOCOL.md`.
+
+pub mod protocol;
+mod rest;
+mod room;
+mod store;
+
+use std::{
+    io,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::{net::TcpListener, sync::mpsc};
+
+use crate::protocol::{ClientMessage, ServerMessage};
+pub use crate::{
+    room::{Lobby, Room, Sender, MAX_PLAYERS},
+    store::{FileStore, GameStore, MemoryStore},
+};
+
+type SharedLobby = Arc<Mutex<Lobby>>;
+
+/// Room code and player number of a connection that joined a room.
+type Seat = (String, usize);
+
+/// Routes of the server, with an empty lobby and the REST games kept in
+/// `store`.
+pub fn app(store: Arc<dyn GameStore>) -> Router {
+    Router::new()
+        .route("/ws", get(upgrade))
+        .with_state(SharedLobby::default())
+        .merge(rest::routes(store))
+}
+
+/// Serve games on `listener` until the process stops.
+pub async fn serve(listener: TcpListener, store: Arc<dyn GameStore>) -> io::Result<()> {
+    axum::serve(listener, app(store)).await
+}
+
+async fn upgrade(ws: WebSocketUpgrade, State(lobby): State<SharedLobby>) -> Response {
+    ws.on_upgrade(move |socket| connection(socket, lobby))
+}
+
+/// Read the messages of one client until it disconnects. Replies go
+/// through a channel, so rooms can reach the client from any connection.
+async fn connection(socket: WebSocket, lobby: SharedLobby) {
+    let (mut sink, mut stream) = socket.split();
+    let (sender, mut receiver) = mpsc::unbounded_channel::<ServerMessage>();
+    let writer = tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            let text = serde_json::to_string(&message).unwrap_or_default();
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut seat = None;
+    while let Some(Ok(message)) = stream.next().await {
+        let result = match message {
+            Message::Text(text) => match serde_json::from_str(text.as_str()) {
+                Ok(message) => handle(&lobby, &mut seat, message, &sender),
+                Err(e) => Err(format!("invalid message: {}", e)),
+            },
+            Message::Close(_) => break,
+            _ => Ok(()),
+        };
+        if let Err(message) = result {
+            sender.send(ServerMessage::Error { message }).ok();
+        }
+    }
+    if let Some((code, player)) = seat {
+        lock(&lobby).leave(&code, player);
+    }
+    writer.abort();
+}
+
+fn handle(
+    lobby: &Mutex<Lobby>,
+    seat: &mut Option<Seat>,
+    message: ClientMessage,
+    sender: &Sender,
+) -> Result<(), String> {
+    let mut lobby = lock(lobby);
+    match (message, &seat) {
+        (ClientMessage::Create { .. } | ClientMessage::Join { .. }, Some(_)) => {
+            Err("already in a room".to_owned())
+        }
+        (
+            ClientMessage::Create {
+                mode,
+                height,
+                width,
+                mines,
+                name,
+            },
+            None,
+        ) => {
+            *seat = Some(lobby.create(mode, (height, width, mines), name, sender.clone())?);
+            Ok(())
+        }
+        (ClientMessage::Join { room, name }, None) => {
+            *seat = Some(lobby.join(&room, name, sender.clone())?);
+            Ok(())
+        }
+        (
+            ClientMessage::Move {
+                action,
+                row,
+                column,
+            },
+            Some((code, player)),
+        ) => lobby.play(code, *player, action, row, column),
+        (ClientMessage::Move { .. }, None) => Err("create or join a room first".to_owned()),
+    }
+}
+
+/// A panic while the lobby is locked only affects one room, the others
+/// keep going.
+fn lock(lobby: &Mutex<Lobby>) -> std::sync::MutexGuard<'_, Lobby> {
+    lobby.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use clap::Arg;
+use tokio::net::TcpListener;
+
+use sweeprs_server::{FileStore, GameStore, MemoryStore};
+
+#[tokio::main]
+async fn main() {
+    let matches = clap::App::new("sweeprs-server")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Host multiplayer minesweeper games over WebSocket and single player games over HTTP, the messages and routes are described in PROTOCOL.md.")
+        .arg(
+            Arg::with_name("address")
+                .short("a")
+                .long("address")
+                .takes_value(true)
+                .default_value("127.0.0.1:7878")
+                .help("Address to listen on, WebSocket clients connect to /ws"),
+        )
+        .arg(
+            Arg::with_name("store")
+                .short("s")
+                .long("store")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Save the HTTP games in DIR, they are only kept in memory otherwise"),
+        )
+        .get_matches();
+    let store: Arc<dyn GameStore> = match matches.value_of("store") {
+        Some(dir) => match FileStore::new(dir) {
+            Ok(store) => Arc::new(store),
+            Err(e) => {
+                eprintln!("error: cannot use {} to save games: {}", dir, e);
+                return;
+            }
+        },
+        None => Arc::new(MemoryStore::default()),
+    };
+    let address = matches.value_of("address").unwrap();
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("error: cannot listen on {}: {}", address, e);
+            return;
+        }
+    };
+    if let Ok(address) = listener.local_addr() {
+        println!("listening on ws://{0}/ws and http://{0}/games", address);
+    }
+    if let Err(e) = sweeprs_server::serve(listener, store).await {
+        eprintln!("error: {}", e);
+    }
+}
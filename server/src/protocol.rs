@@ -0,0 +1,206 @@
+//! Messages exchanged over the WebSocket, one JSON object per text frame
+//! with its kind in `type`. See `PROTOCOL.md` for the whole exchange.
+
+use serde::{Deserialize, Serialize};
+
+use sweeprs::{BoardResult, BoardState, CellView, Pos, SweeperBoard};
+
+/// How the players of a room share the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Everyone plays on the same board.
+    Coop,
+    /// Every player has a board with the same mines, the first to clear it
+    /// wins.
+    Versus,
+}
+
+/// `open`, `flag` or `chord` on the wire, checked by the engine like in
+/// every other front end.
+pub use sweeprs::Move as Action;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameState {
+    /// No cell opened yet, the mines are not placed.
+    New,
+    Playing,
+    Won,
+    Lost,
+}
+
+impl GameState {
+    pub fn of<B: SweeperBoard>(board: &B) -> Self {
+        match board.state() {
+            BoardState::Uninitialized => Self::New,
+            BoardState::Playing => Self::Playing,
+            BoardState::Finished(BoardResult::Win) => Self::Won,
+            BoardState::Finished(BoardResult::Lost) => Self::Lost,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Open a room and join it as its first player.
+    Create {
+        mode: Mode,
+        #[serde(default = "default_height")]
+        height: usize,
+        #[serde(default = "default_width")]
+        width: usize,
+        #[serde(default = "default_mines")]
+        mines: usize,
+        name: String,
+    },
+    Join {
+        room: String,
+        name: String,
+    },
+    Move {
+        action: Action,
+        row: usize,
+        column: usize,
+    },
+}
+
+/// Most cells of a board made for a client, boards are sized by whoever
+/// asks for them.
+pub const MAX_CELLS: usize = 256 * 256;
+
+/// Refuse a board of more than `MAX_CELLS` cells before it is made.
+pub fn check_size(height: usize, width: usize) -> Result<(), String> {
+    match height.checked_mul(width) {
+        Some(cells) if cells <= MAX_CELLS => Ok(()),
+        _ => Err(format!("board has more than {} cells", MAX_CELLS)),
+    }
+}
+
+pub(crate) fn default_height() -> usize {
+    9
+}
+
+pub(crate) fn default_width() -> usize {
+    9
+}
+
+pub(crate) fn default_mines() -> usize {
+    10
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Sent to a player entering a room, with the names of everyone in it,
+    /// `None` for the players who left.
+    Joined {
+        room: String,
+        player: usize,
+        mode: Mode,
+        height: usize,
+        width: usize,
+        mines: usize,
+        players: Vec<Option<String>>,
+    },
+    PlayerJoined {
+        player: usize,
+        name: String,
+    },
+    PlayerLeft {
+        player: usize,
+    },
+    /// A move accepted by the server, sent to everyone in the room.
+    Move {
+        player: usize,
+        action: Action,
+        row: usize,
+        column: usize,
+    },
+    /// The board of the player receiving it, shared by everyone in co-op.
+    Board {
+        board: BoardView,
+    },
+    /// How far a player got in versus, without showing their board.
+    Progress {
+        player: usize,
+        opened: usize,
+        state: GameState,
+    },
+    /// The game is over. `winner` is only set in versus.
+    Finished {
+        state: GameState,
+        winner: Option<usize>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// What a player can see of a board, mines are only shown once it is
+/// finished.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct BoardView {
+    pub state: GameState,
+    pub mines_left: isize,
+    /// One string per row, `#` closed, `F` flagged, `.` empty, `1` to `8`
+    /// numbers and `*` mines.
+    pub rows: Vec<String>,
+}
+
+impl BoardView {
+    pub fn of<B: SweeperBoard>(board: &B) -> Self {
+        let rows = (0..board.height())
+            .map(|i| {
+                (0..board.width())
+                    .map(|j| symbol(board, Pos::new(i, j)))
+                    .collect()
+            })
+            .collect();
+        Self {
+            state: GameState::of(board),
+            mines_left: board.remaining_flags(),
+            rows,
+        }
+    }
+}
+
+fn symbol<B: SweeperBoard>(board: &B, pos: Pos) -> char {
+    match board.view(pos) {
+        CellView::Closed => '#',
+        CellView::Flagged => 'F',
+        CellView::Mine => '*',
+        CellView::Number(0) => '.',
+        CellView::Number(mine_count) => char::from_digit(mine_count as u32, 10).unwrap_or('?'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wire_format() {
+        let create: ClientMessage =
+            serde_json::from_str(r#"{"type":"create","mode":"versus","name":"ann"}"#).unwrap();
+        assert_eq!(
+            create,
+            ClientMessage::Create {
+                mode: Mode::Versus,
+                height: 9,
+                width: 9,
+                mines: 10,
+                name: "ann".to_owned(),
+            }
+        );
+        let finished = ServerMessage::Finished {
+            state: GameState::Won,
+            winner: Some(1),
+        };
+        assert_eq!(
+            serde_json::to_string(&finished).unwrap(),
+            r#"{"type":"finished","state":"won","winner":1}"#
+        );
+    }
+}
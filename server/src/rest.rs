@@ -0,0 +1,224 @@
+//! Single player games over HTTP, kept in a `GameStore` between requests.
+//! The routes are described in `PROTOCOL.md`.
+
+use std::{io, sync::Arc};
+
+use axum::{
+    extract::{
+        rejection::{JsonRejection, PathRejection},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use sweeprs::{Board, Error, Pos, Refused, SweeperBoard};
+
+use crate::{
+    protocol::{check_size, default_height, default_mines, default_width, Action, BoardView},
+    store::GameStore,
+};
+
+type SharedStore = Arc<dyn GameStore>;
+
+#[derive(Deserialize)]
+struct NewGame {
+    #[serde(default = "default_height")]
+    height: usize,
+    #[serde(default = "default_width")]
+    width: usize,
+    #[serde(default = "default_mines")]
+    mines: usize,
+    /// Random when not given.
+    seed: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    row: usize,
+    column: usize,
+}
+
+/// A game as its player sees it.
+#[derive(Serialize)]
+struct Game {
+    id: String,
+    #[serde(flatten)]
+    board: BoardView,
+}
+
+/// Why a request failed, answered as `{"error": "..."}`.
+#[derive(Debug)]
+enum ApiError {
+    Engine(Error),
+    /// The body or the path cannot be read.
+    BadRequest(String),
+    /// The board asked for has more than `MAX_CELLS` cells.
+    TooLarge(String),
+    NotFound,
+    /// The move does not apply to the cell or the game.
+    Refused(String),
+    Storage(io::Error),
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        Self::Engine(e)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<io::Error> for ApiError {
+    fn from(e: io::Error) -> Self {
+        Self::Storage(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::Engine(e @ Error::InvalidConfigError) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, e.to_string())
+            }
+            ApiError::Engine(e @ Error::IndexOutOfBoundError) => {
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::TooLarge(message) => (StatusCode::UNPROCESSABLE_ENTITY, message),
+            ApiError::NotFound => (StatusCode::NOT_FOUND, "no such game".to_owned()),
+            ApiError::Refused(message) => (StatusCode::CONFLICT, message),
+            // the details stay in the server logs
+            ApiError::Storage(e) => {
+                eprintln!("error: cannot access a game: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "cannot access the game".to_owned(),
+                )
+            }
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+/// Routes of the REST API, playing the games kept in `store`.
+pub fn routes(store: SharedStore) -> Router {
+    Router::new()
+        .route("/games", post(create))
+        .route("/games/{id}", get(show))
+        .route("/games/{id}/{action}", post(play))
+        .with_state(store)
+}
+
+/// Rejections of the extractors are taken as results, so they are answered
+/// like every other error.
+async fn create(
+    State(store): State<SharedStore>,
+    game: Result<Json<NewGame>, JsonRejection>,
+) -> Result<(StatusCode, Json<Game>), ApiError> {
+    let Json(game) = game?;
+    check_size(game.height, game.width).map_err(ApiError::TooLarge)?;
+    let board = match game.seed {
+        Some(seed) => Board::with_seed(game.height, game.width, game.mines, seed)?,
+        None => Board::new(game.height, game.width, game.mines)?,
+    };
+    let view = BoardView::of(&board);
+    let id = blocking(move || store.create(board)).await?;
+    Ok((StatusCode::CREATED, Json(Game { id, board: view })))
+}
+
+async fn show(
+    State(store): State<SharedStore>,
+    Path(id): Path<String>,
+) -> Result<Json<Game>, ApiError> {
+    let (id, view) = blocking(move || {
+        let mut view = None;
+        store.read(&id, &mut |board| view = Some(BoardView::of(board)))?;
+        Ok((id, view))
+    })
+    .await?;
+    let board = view.ok_or(ApiError::NotFound)?;
+    Ok(Json(Game { id, board }))
+}
+
+/// `action` is the last part of the path, `open`, `flag` or `chord`.
+async fn play(
+    State(store): State<SharedStore>,
+    path: Result<Path<(String, Action)>, PathRejection>,
+    target: Result<Json<Target>, JsonRejection>,
+) -> Result<Json<Game>, ApiError> {
+    let (Path((id, action)), Json(target)) = (path?, target?);
+    let (id, result) = blocking(move || {
+        let mut result = Err(ApiError::NotFound);
+        store.write(&id, &mut |board| {
+            result = play_move(board, action, &target).map(|()| BoardView::of(board));
+        })?;
+        Ok((id, result))
+    })
+    .await?;
+    Ok(Json(Game { id, board: result? }))
+}
+
+/// Stores may wait on files, they are kept off the threads serving the
+/// other requests.
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    tokio::task::spawn_blocking(f).await?
+}
+
+fn play_move(board: &mut Board, action: Action, target: &Target) -> Result<(), ApiError> {
+    match board.play(action, Pos::new(target.row, target.column)) {
+        Ok(()) => Ok(()),
+        Err(Refused::OutOfBound) => Err(Error::IndexOutOfBoundError.into()),
+        Err(refused) => Err(ApiError::Refused(refused.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes() {
+        let status = |error: ApiError| error.into_response().status();
+        assert_eq!(
+            status(Error::InvalidConfigError.into()),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(Error::IndexOutOfBoundError.into()),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(ApiError::BadRequest("missing field".to_owned())),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(ApiError::TooLarge("too many cells".to_owned())),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(status(ApiError::NotFound), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status(ApiError::Refused("cell is open".to_owned())),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(io::Error::other("disk is full").into()),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
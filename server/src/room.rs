@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use tokio::sync::mpsc;
+
+use sweeprs::{Board, BoardState, CellState, Pos, Refused, SweeperBoard, SweeperCell};
+
+use crate::protocol::{check_size, Action, BoardView, GameState, Mode, ServerMessage};
+
+/// Most players a room accepts.
+pub const MAX_PLAYERS: usize = 8;
+
+/// Letters of a room code.
+const CODE_LENGTH: usize = 4;
+
+/// Where the messages for one connection go.
+pub type Sender = mpsc::UnboundedSender<ServerMessage>;
+
+struct Member {
+    name: String,
+    sender: Sender,
+}
+
+/// Players sharing a game and the boards they play on. Every move goes
+/// through here, so clients only ever see what the rules let them see.
+pub struct Room {
+    code: String,
+    mode: Mode,
+    height: usize,
+    width: usize,
+    mines: usize,
+    seed: u64,
+    /// A single board in co-op, one per player in versus with the same
+    /// seed, indexed like `players`.
+    boards: Vec<Board>,
+    /// `None` once a player left, so the others keep their number.
+    players: Vec<Option<Member>>,
+    over: bool,
+}
+
+impl Room {
+    /// The size of the boards comes from a client, it is checked before
+    /// any board is made.
+    pub fn new(
+        code: String,
+        mode: Mode,
+        (height, width, mines): (usize, usize, usize),
+        seed: u64,
+    ) -> Result<Self, String> {
+        check_size(height, width)?;
+        let board = Board::with_seed(height, width, mines, seed).map_err(|e| e.to_string())?;
+        Ok(Self {
+            code,
+            mode,
+            height,
+            width,
+            mines,
+            seed,
+            boards: match mode {
+                Mode::Coop => vec![board],
+                Mode::Versus => Vec::new(),
+            },
+            players: Vec::new(),
+            over: false,
+        })
+    }
+
+    /// Add a player, returning their number. Versus rooms are closed once
+    /// the first cell is opened, the boards would no longer match.
+    pub fn join(&mut self, name: String, sender: Sender) -> Result<usize, String> {
+        if self.players.len() >= MAX_PLAYERS {
+            return Err("room is full".to_owned());
+        }
+        if self.mode == Mode::Versus {
+            if self.started() {
+                return Err("game already started".to_owned());
+            }
+            let board = Board::with_seed(self.height, self.width, self.mines, self.seed)
+                .map_err(|e| e.to_string())?;
+            self.boards.push(board);
+        }
+        let player = self.players.len();
+        self.broadcast(ServerMessage::PlayerJoined {
+            player,
+            name: name.clone(),
+        });
+        self.players.push(Some(Member { name, sender }));
+        let players = self
+            .players
+            .iter()
+            .map(|member| member.as_ref().map(|member| member.name.clone()))
+            .collect();
+        self.send(
+            player,
+            ServerMessage::Joined {
+                room: self.code.clone(),
+                player,
+                mode: self.mode,
+                height: self.height,
+                width: self.width,
+                mines: self.mines,
+                players,
+            },
+        );
+        self.send_board(player);
+        Ok(player)
+    }
+
+    /// In versus the game can end when a player leaves, if everyone still
+    /// there already hit a mine.
+    pub fn leave(&mut self, player: usize) {
+        if let Some(member) = self.players.get_mut(player) {
+            *member = None;
+            self.broadcast(ServerMessage::PlayerLeft { player });
+            self.end_if_finished();
+        }
+    }
+
+    /// No one is left in the room.
+    pub fn is_empty(&self) -> bool {
+        self.players.iter().all(Option::is_none)
+    }
+
+    /// Check and apply a move, then tell everyone what changed. Nothing is
+    /// sent when the move is refused.
+    pub fn play(
+        &mut self,
+        player: usize,
+        action: Action,
+        row: usize,
+        column: usize,
+    ) -> Result<(), String> {
+        if self.over {
+            return Err("game is over".to_owned());
+        }
+        let index = match self.mode {
+            Mode::Coop => 0,
+            Mode::Versus => player,
+        };
+        let pos = Pos::new(row, column);
+        let first = !self.started();
+        self.boards[index]
+            .play(action, pos)
+            .map_err(|refused| match refused {
+                Refused::OutOfBound => format!("({}, {}) is outside of the board", row, column),
+                Refused::GameOver => "your game is over".to_owned(),
+                refused => refused.to_string(),
+            })?;
+        // in versus the first cell is opened on every board, so the mines
+        // end up at the same places
+        let changed: Vec<usize> = match (self.mode, action) {
+            (Mode::Versus, Action::Open) if first => (0..self.boards.len()).collect(),
+            _ => vec![index],
+        };
+        for &other in changed.iter().filter(|&&other| other != index) {
+            let board = &mut self.boards[other];
+            // a flag there would keep the mines from being placed, the
+            // cell is safe anyway
+            if let CellState::Flagged = board.cell(pos).state() {
+                board.flag(pos);
+            }
+            board.open(pos);
+        }
+        self.broadcast(ServerMessage::Move {
+            player,
+            action,
+            row,
+            column,
+        });
+        match self.mode {
+            Mode::Coop => {
+                for player in 0..self.players.len() {
+                    self.send_board(player);
+                }
+            }
+            Mode::Versus => {
+                for &changed in &changed {
+                    self.send_board(changed);
+                    let board = &self.boards[changed];
+                    self.broadcast(ServerMessage::Progress {
+                        player: changed,
+                        opened: opened_count(board),
+                        state: GameState::of(board),
+                    });
+                }
+            }
+        }
+        self.end_if_finished();
+        Ok(())
+    }
+
+    /// A cell was opened on one of the boards, the mines are placed.
+    fn started(&self) -> bool {
+        self.boards
+            .iter()
+            .any(|board| !matches!(board.state(), BoardState::Uninitialized))
+    }
+
+    fn end_if_finished(&mut self) {
+        if self.over {
+            return;
+        }
+        if let Some(finished) = self.result() {
+            self.over = true;
+            self.broadcast(finished);
+        }
+    }
+
+    /// How the game ended, if it did. Versus is won by the first player to
+    /// clear their board, and lost once every player still there hit a
+    /// mine.
+    fn result(&self) -> Option<ServerMessage> {
+        match self.mode {
+            Mode::Coop => match GameState::of(&self.boards[0]) {
+                state @ (GameState::Won | GameState::Lost) => Some(ServerMessage::Finished {
+                    state,
+                    winner: None,
+                }),
+                _ => None,
+            },
+            Mode::Versus => {
+                let states: Vec<(usize, GameState)> = self
+                    .players
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, member)| member.is_some())
+                    .map(|(player, _)| (player, GameState::of(&self.boards[player])))
+                    .collect();
+                if let Some(&(winner, _)) =
+                    states.iter().find(|(_, state)| *state == GameState::Won)
+                {
+                    Some(ServerMessage::Finished {
+                        state: GameState::Won,
+                        winner: Some(winner),
+                    })
+                } else if states.iter().all(|(_, state)| *state == GameState::Lost) {
+                    Some(ServerMessage::Finished {
+                        state: GameState::Lost,
+                        winner: None,
+                    })
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// The board `player` plays on, as they are allowed to see it.
+    fn send_board(&self, player: usize) {
+        let board = match self.mode {
+            Mode::Coop => &self.boards[0],
+            Mode::Versus => &self.boards[player],
+        };
+        self.send(
+            player,
+            ServerMessage::Board {
+                board: BoardView::of(board),
+            },
+        );
+    }
+
+    /// A player who is gone or whose connection dropped is skipped, their
+    /// seat is freed when the connection is closed.
+    fn send(&self, player: usize, message: ServerMessage) {
+        if let Some(Some(member)) = self.players.get(player) {
+            member.sender.send(message).ok();
+        }
+    }
+
+    fn broadcast(&self, message: ServerMessage) {
+        for member in self.players.iter().flatten() {
+            member.sender.send(message.clone()).ok();
+        }
+    }
+}
+
+fn opened_count(board: &Board) -> usize {
+    board
+        .iter()
+        .filter(|(_, cell)| matches!(cell.state(), CellState::Opened))
+        .count()
+}
+
+/// Every room of the server, by code.
+#[derive(Default)]
+pub struct Lobby {
+    rooms: HashMap<String, Room>,
+}
+
+impl Lobby {
+    /// Open a room with a fresh code and seed, and join it. Returns the
+    /// code and the number of the player.
+    pub fn create(
+        &mut self,
+        mode: Mode,
+        dimension: (usize, usize, usize),
+        name: String,
+        sender: Sender,
+    ) -> Result<(String, usize), String> {
+        let mut rng = rand::thread_rng();
+        let code = loop {
+            let code: String = (0..CODE_LENGTH)
+                .map(|_| rng.gen_range(b'A'..=b'Z') as char)
+                .collect();
+            if !self.rooms.contains_key(&code) {
+                break code;
+            }
+        };
+        let mut room = Room::new(code.clone(), mode, dimension, rng.gen())?;
+        let player = room.join(name, sender)?;
+        self.rooms.insert(code.clone(), room);
+        Ok((code, player))
+    }
+
+    /// Codes are not case sensitive.
+    pub fn join(
+        &mut self,
+        code: &str,
+        name: String,
+        sender: Sender,
+    ) -> Result<(String, usize), String> {
+        let code = code.to_uppercase();
+        let room = self
+            .rooms
+            .get_mut(&code)
+            .ok_or_else(|| format!("no room {}", code))?;
+        let player = room.join(name, sender)?;
+        Ok((code, player))
+    }
+
+    pub fn play(
+        &mut self,
+        code: &str,
+        player: usize,
+        action: Action,
+        row: usize,
+        column: usize,
+    ) -> Result<(), String> {
+        let room = self.rooms.get_mut(code).ok_or("room is closed")?;
+        room.play(player, action, row, column)
+    }
+
+    /// The room is closed once the last player leaves.
+    pub fn leave(&mut self, code: &str, player: usize) {
+        if let Some(room) = self.rooms.get_mut(code) {
+            room.leave(player);
+            if room.is_empty() {
+                self.rooms.remove(code);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    use super::*;
+
+    fn drain(receiver: &mut UnboundedReceiver<ServerMessage>) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        while let Ok(message) = receiver.try_recv() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn boards(messages: &[ServerMessage]) -> Vec<&BoardView> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                ServerMessage::Board { board } => Some(board),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn coop_shares_the_board() {
+        let mut room = Room::new("ABCD".to_owned(), Mode::Coop, (9, 9, 10), 1).unwrap();
+        let (ann, mut ann_inbox) = unbounded_channel();
+        let (bob, mut bob_inbox) = unbounded_channel();
+        assert_eq!(room.join("ann".to_owned(), ann), Ok(0));
+        assert_eq!(room.join("bob".to_owned(), bob), Ok(1));
+        assert!(
+            drain(&mut ann_inbox).contains(&ServerMessage::PlayerJoined {
+                player: 1,
+                name: "bob".to_owned()
+            })
+        );
+        drain(&mut bob_inbox);
+        room.play(1, Action::Open, 4, 4).unwrap();
+        let (ann_seen, bob_seen) = (drain(&mut ann_inbox), drain(&mut bob_inbox));
+        assert_eq!(boards(&ann_seen), boards(&bob_seen));
+        let board = boards(&ann_seen)[0];
+        assert_eq!(board.state, GameState::Playing);
+        // mines stay hidden while the game is going on
+        assert!(board.rows.iter().all(|row| !row.contains('*')));
+        assert_eq!(
+            room.play(0, Action::Open, 4, 4),
+            Err("cell is already open".to_owned())
+        );
+        assert!(room.play(0, Action::Flag, 9, 0).is_err());
+        assert!(drain(&mut ann_inbox).is_empty());
+    }
+
+    #[test]
+    fn versus_same_mines_separate_boards() {
+        let mut room = Room::new("ABCD".to_owned(), Mode::Versus, (9, 9, 10), 1).unwrap();
+        let (ann, mut ann_inbox) = unbounded_channel();
+        let (bob, mut bob_inbox) = unbounded_channel();
+        room.join("ann".to_owned(), ann).unwrap();
+        room.join("bob".to_owned(), bob).unwrap();
+        drain(&mut ann_inbox);
+        drain(&mut bob_inbox);
+        room.play(0, Action::Open, 4, 4).unwrap();
+        let (ann_seen, bob_seen) = (drain(&mut ann_inbox), drain(&mut bob_inbox));
+        assert_eq!(boards(&ann_seen), boards(&bob_seen));
+        let (carl, _) = unbounded_channel();
+        assert!(room.join("carl".to_owned(), carl).is_err());
+        // from now on every player only gets their own board
+        let closed = boards(&ann_seen)[0]
+            .rows
+            .iter()
+            .enumerate()
+            .find_map(|(i, row)| row.chars().position(|symbol| symbol == '#').map(|j| (i, j)));
+        let (row, column) = closed.unwrap();
+        room.play(0, Action::Flag, row, column).unwrap();
+        let (ann_seen, bob_seen) = (drain(&mut ann_inbox), drain(&mut bob_inbox));
+        assert_eq!(boards(&ann_seen)[0].mines_left, 9);
+        assert!(boards(&bob_seen).is_empty());
+        assert!(bob_seen
+            .iter()
+            .any(|message| matches!(message, ServerMessage::Progress { player: 0, .. })));
+    }
+
+    #[test]
+    fn versus_flag_before_first_open() {
+        let mut room = Room::new("ABCD".to_owned(), Mode::Versus, (9, 9, 10), 1).unwrap();
+        let (ann, _ann_inbox) = unbounded_channel();
+        let (bob, _bob_inbox) = unbounded_channel();
+        room.join("ann".to_owned(), ann).unwrap();
+        room.join("bob".to_owned(), bob).unwrap();
+        room.play(1, Action::Flag, 4, 4).unwrap();
+        room.play(0, Action::Open, 4, 4).unwrap();
+        let mines = |board: &Board| {
+            board
+                .iter()
+                .filter(|(_, cell)| matches!(cell.kind(), sweeprs::CellKind::Mine))
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(mines(&room.boards[0]), mines(&room.boards[1]));
+        assert_eq!(
+            BoardView::of(&room.boards[0]),
+            BoardView::of(&room.boards[1])
+        );
+    }
+
+    #[test]
+    fn versus_lost_by_everyone() {
+        let mut room = Room::new("ABCD".to_owned(), Mode::Versus, (9, 9, 10), 1).unwrap();
+        let (ann, mut ann_inbox) = unbounded_channel();
+        room.join("ann".to_owned(), ann).unwrap();
+        room.play(0, Action::Open, 4, 4).unwrap();
+        let mine = room.boards[0]
+            .iter()
+            .find(|(_, cell)| matches!(cell.kind(), sweeprs::CellKind::Mine))
+            .map(|(pos, _)| pos)
+            .unwrap();
+        room.play(0, Action::Open, mine.i, mine.j).unwrap();
+        assert!(drain(&mut ann_inbox).contains(&ServerMessage::Finished {
+            state: GameState::Lost,
+            winner: None
+        }));
+        assert_eq!(
+            room.play(0, Action::Open, 0, 0),
+            Err("game is over".to_owned())
+        );
+    }
+
+    #[test]
+    fn versus_lost_when_the_last_player_leaves() {
+        let mut room = Room::new("ABCD".to_owned(), Mode::Versus, (9, 9, 10), 1).unwrap();
+        let (ann, mut ann_inbox) = unbounded_channel();
+        let (bob, _bob_inbox) = unbounded_channel();
+        room.join("ann".to_owned(), ann).unwrap();
+        room.join("bob".to_owned(), bob).unwrap();
+        room.play(0, Action::Open, 4, 4).unwrap();
+        let mine = room.boards[0]
+            .iter()
+            .find(|(_, cell)| matches!(cell.kind(), sweeprs::CellKind::Mine))
+            .map(|(pos, _)| pos)
+            .unwrap();
+        room.play(0, Action::Open, mine.i, mine.j).unwrap();
+        let finished = ServerMessage::Finished {
+            state: GameState::Lost,
+            winner: None,
+        };
+        assert!(!drain(&mut ann_inbox).contains(&finished));
+        // bob was the only one still playing
+        room.leave(1);
+        assert!(drain(&mut ann_inbox).contains(&finished));
+        assert!(room.over);
+    }
+
+    #[test]
+    fn too_large() {
+        let room = Room::new("ABCD".to_owned(), Mode::Coop, (100_000, 100_000, 10), 1);
+        assert_eq!(
+            room.err(),
+            Some("board has more than 65536 cells".to_owned())
+        );
+        assert!(Room::new("ABCD".to_owned(), Mode::Coop, (usize::MAX, 2, 10), 1).is_err());
+    }
+
+    #[test]
+    fn lobby_rooms() {
+        let mut lobby = Lobby::default();
+        let (ann, _ann_inbox) = unbounded_channel();
+        let (bob, _bob_inbox) = unbounded_channel();
+        let (code, player) = lobby
+            .create(Mode::Coop, (9, 9, 10), "ann".to_owned(), ann)
+            .unwrap();
+        assert_eq!(player, 0);
+        assert_eq!(
+            lobby.join(&code.to_lowercase(), "bob".to_owned(), bob),
+            Ok((code.clone(), 1))
+        );
+        let (carl, _) = unbounded_channel();
+        assert!(lobby.join("????", "carl".to_owned(), carl).is_err());
+        let (dave, _) = unbounded_channel();
+        assert!(lobby
+            .create(Mode::Coop, (2, 2, 10), "dave".to_owned(), dave)
+            .is_err());
+        lobby.leave(&code, 0);
+        lobby.leave(&code, 1);
+        assert!(lobby.rooms.is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use sweeprs::Board;
+
+/// Where the games of the REST API are kept between requests.
+pub trait GameStore: Send + Sync {
+    /// Keep a new game, returning its id.
+    fn create(&self, board: Board) -> io::Result<String>;
+
+    /// Run `f` on the game `id`, `f` is not called when there is none.
+    fn read(&self, id: &str, f: &mut dyn FnMut(&Board)) -> io::Result<()>;
+
+    /// Run `f` on the game `id` and keep what it changed, `f` is not called
+    /// when there is none. No other access to the game happens meanwhile.
+    fn write(&self, id: &str, f: &mut dyn FnMut(&mut Board)) -> io::Result<()>;
+}
+
+/// 16 hex digits, safe to use as a file name.
+fn new_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// A panic while a store is locked only affects the request that caused it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Games kept until the server stops.
+#[derive(Default)]
+pub struct MemoryStore {
+    games: Mutex<HashMap<String, Board>>,
+}
+
+impl GameStore for MemoryStore {
+    fn create(&self, board: Board) -> io::Result<String> {
+        let mut games = lock(&self.games);
+        let id = loop {
+            let id = new_id();
+            if !games.contains_key(&id) {
+                break id;
+            }
+        };
+        games.insert(id.clone(), board);
+        Ok(id)
+    }
+
+    fn read(&self, id: &str, f: &mut dyn FnMut(&Board)) -> io::Result<()> {
+        if let Some(board) = lock(&self.games).get(id) {
+            f(board);
+        }
+        Ok(())
+    }
+
+    fn write(&self, id: &str, f: &mut dyn FnMut(&mut Board)) -> io::Result<()> {
+        if let Some(board) = lock(&self.games).get_mut(id) {
+            f(board);
+        }
+        Ok(())
+    }
+}
+
+/// Locks of a `FileStore`, games share one when their ids hash to it.
+const LOCK_SLOTS: usize = 32;
+
+/// Games saved as `<id>.json` in a directory, kept across restarts.
+pub struct FileStore {
+    dir: PathBuf,
+    /// One is held while a game is read and saved back, so moves on the
+    /// same game are not lost while other games do not wait.
+    locks: [Mutex<()>; LOCK_SLOTS],
+}
+
+impl FileStore {
+    /// Create `dir` if it does not exist yet.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            locks: Default::default(),
+        })
+    }
+
+    fn lock(&self, id: &str) -> MutexGuard<'_, ()> {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        lock(&self.locks[hasher.finish() as usize % LOCK_SLOTS])
+    }
+
+    /// `None` for ids this store could not have made, which keeps the
+    /// requests inside `dir`.
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        if id.len() == 16 && id.bytes().all(|b| b.is_ascii_hexdigit()) {
+            Some(self.dir.join(format!("{}.json", id)))
+        } else {
+            None
+        }
+    }
+
+    fn load(&self, id: &str) -> io::Result<Option<(PathBuf, Board)>> {
+        let path = match self.path(id) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        match fs::read(&path) {
+            // the board is checked while it is read, see `Board`
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map(|board| Some((path, board)))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write to a temporary file first, a crash never leaves half a game.
+    fn save(&self, path: &Path, board: &Board) -> io::Result<()> {
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec(board)?)?;
+        fs::rename(temporary, path)
+    }
+}
+
+impl GameStore for FileStore {
+    fn create(&self, board: Board) -> io::Result<String> {
+        loop {
+            let id = new_id();
+            let _lock = self.lock(&id);
+            let path = self.dir.join(format!("{}.json", id));
+            if !path.exists() {
+                self.save(&path, &board)?;
+                return Ok(id);
+            }
+        }
+    }
+
+    fn read(&self, id: &str, f: &mut dyn FnMut(&Board)) -> io::Result<()> {
+        let _lock = self.lock(id);
+        if let Some((_, board)) = self.load(id)? {
+            f(&board);
+        }
+        Ok(())
+    }
+
+    fn write(&self, id: &str, f: &mut dyn FnMut(&mut Board)) -> io::Result<()> {
+        let _lock = self.lock(id);
+        if let Some((path, mut board)) = self.load(id)? {
+            f(&mut board);
+            self.save(&path, &board)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sweeprs::{Pos, SweeperBoard};
+
+    use super::*;
+
+    fn flags(store: &dyn GameStore, id: &str) -> Option<usize> {
+        let mut flags = None;
+        store
+            .read(id, &mut |board| flags = Some(board.flag_count()))
+            .unwrap();
+        flags
+    }
+
+    fn round_trip(store: &dyn GameStore) {
+        let id = store
+            .create(Board::with_seed(9, 9, 10, 1).unwrap())
+            .unwrap();
+        assert_eq!(flags(store, &id), Some(0));
+        store
+            .write(&id, &mut |board| {
+                board.flag(Pos::new(0, 0));
+            })
+            .unwrap();
+        assert_eq!(flags(store, &id), Some(1));
+        assert_eq!(flags(store, "0123456789abcdef"), None);
+    }
+
+    #[test]
+    fn memory() {
+        round_trip(&MemoryStore::default());
+    }
+
+    #[test]
+    fn file() {
+        let dir = std::env::temp_dir().join(format!("sweeprs-store-{}", std::process::id()));
+        let store = FileStore::new(&dir).unwrap();
+        round_trip(&store);
+        // every game is in its own file, kept by another store on the same
+        // directory
+        let id = store.create(Board::new(9, 9, 10).unwrap()).unwrap();
+        assert_eq!(flags(&FileStore::new(&dir).unwrap(), &id), Some(0));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert_eq!(flags(&store, "../../etc/passwd"), None);
+        // a game edited by hand into a board that cannot be played
+        let path = store.path(&id).unwrap();
+        let mut saved: serde_json::Value =
+            serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        saved["cells"][0].as_array_mut().unwrap().pop();
+        fs::write(&path, saved.to_string()).unwrap();
+        let error = store.read(&id, &mut |_| ()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
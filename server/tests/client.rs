@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use sweeprs_server::{
+    protocol::{Action, ClientMessage, GameState, Mode, ServerMessage},
+    MemoryStore,
+};
+
+/// Client speaking the protocol to a server running in the same process.
+struct Client {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl Client {
+    async fn connect(address: &str) -> Self {
+        let (socket, _) = connect_async(format!("ws://{}/ws", address)).await.unwrap();
+        Self { socket }
+    }
+
+    async fn send(&mut self, message: ClientMessage) {
+        let text = serde_json::to_string(&message).unwrap();
+        self.socket.send(Message::Text(text.into())).await.unwrap();
+    }
+
+    async fn receive(&mut self) -> ServerMessage {
+        loop {
+            match self.socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => return serde_json::from_str(text.as_str()).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Skip messages until one matches.
+    async fn receive_until(&mut self, wanted: impl Fn(&ServerMessage) -> bool) -> ServerMessage {
+        loop {
+            let message = self.receive().await;
+            if wanted(&message) {
+                return message;
+            }
+        }
+    }
+}
+
+async fn start() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    tokio::spawn(sweeprs_server::serve(
+        listener,
+        Arc::new(MemoryStore::default()),
+    ));
+    address
+}
+
+async fn create(client: &mut Client, mode: Mode) -> String {
+    client
+        .send(ClientMessage::Create {
+            mode,
+            height: 9,
+            width: 9,
+            mines: 10,
+            name: "ann".to_owned(),
+        })
+        .await;
+    match client.receive().await {
+        ServerMessage::Joined { room, player, .. } => {
+            assert_eq!(player, 0);
+            room
+        }
+        message => panic!("unexpected {:?}", message),
+    }
+}
+
+#[tokio::test]
+async fn coop_game() {
+    let address = start().await;
+    let mut ann = Client::connect(&address).await;
+    let mut bob = Client::connect(&address).await;
+    let room = create(&mut ann, Mode::Coop).await;
+    bob.send(ClientMessage::Join {
+        room,
+        name: "bob".to_owned(),
+    })
+    .await;
+    match bob.receive().await {
+        ServerMessage::Joined {
+            player, players, ..
+        } => {
+            assert_eq!(player, 1);
+            assert_eq!(
+                players,
+                vec![Some("ann".to_owned()), Some("bob".to_owned())]
+            );
+        }
+        message => panic!("unexpected {:?}", message),
+    }
+    ann.receive_until(|message| matches!(message, ServerMessage::PlayerJoined { player: 1, .. }))
+        .await;
+    bob.send(ClientMessage::Move {
+        action: Action::Open,
+        row: 4,
+        column: 4,
+    })
+    .await;
+    let moved = ann
+        .receive_until(|message| matches!(message, ServerMessage::Move { .. }))
+        .await;
+    assert_eq!(
+        moved,
+        ServerMessage::Move {
+            player: 1,
+            action: Action::Open,
+            row: 4,
+            column: 4
+        }
+    );
+    // both got the empty board when joining, then the opened one
+    let is_board = |message: &ServerMessage| matches!(message, ServerMessage::Board { board } if board.state == GameState::Playing);
+    assert_eq!(
+        ann.receive_until(is_board).await,
+        bob.receive_until(is_board).await
+    );
+    ann.send(ClientMessage::Move {
+        action: Action::Chord,
+        row: 9,
+        column: 9,
+    })
+    .await;
+    assert!(matches!(ann.receive().await, ServerMessage::Error { .. }));
+    drop(bob);
+    ann.receive_until(|message| matches!(message, ServerMessage::PlayerLeft { player: 1 }))
+        .await;
+}
+
+#[tokio::test]
+async fn versus_game() {
+    let address = start().await;
+    let mut ann = Client::connect(&address).await;
+    let mut bob = Client::connect(&address).await;
+    let room = create(&mut ann, Mode::Versus).await;
+    bob.send(ClientMessage::Join {
+        room: room.clone(),
+        name: "bob".to_owned(),
+    })
+    .await;
+    bob.receive_until(|message| matches!(message, ServerMessage::Joined { .. }))
+        .await;
+    ann.send(ClientMessage::Move {
+        action: Action::Open,
+        row: 0,
+        column: 0,
+    })
+    .await;
+    let progress = bob
+        .receive_until(|message| matches!(message, ServerMessage::Progress { player: 1, .. }))
+        .await;
+    assert!(matches!(
+        progress,
+        ServerMessage::Progress {
+            state: GameState::Playing,
+            ..
+        }
+    ));
+    let mut carl = Client::connect(&address).await;
+    carl.send(ClientMessage::Join {
+        room,
+        name: "carl".to_owned(),
+    })
+    .await;
+    assert_eq!(
+        carl.receive().await,
+        ServerMessage::Error {
+            message: "game already started".to_owned()
+        }
+    );
+}
+
+#[tokio::test]
+async fn invalid_messages() {
+    let address = start().await;
+    let mut client = Client::connect(&address).await;
+    client
+        .socket
+        .send(Message::Text("{\"type\":\"dance\"}".into()))
+        .await
+        .unwrap();
+    assert!(matches!(
+        client.receive().await,
+        ServerMessage::Error { .. }
+    ));
+    client
+        .send(ClientMessage::Move {
+            action: Action::Flag,
+            row: 0,
+            column: 0,
+        })
+        .await;
+    assert_eq!(
+        client.receive().await,
+        ServerMessage::Error {
+            message: "create or join a room first".to_owned()
+        }
+    );
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use sweeprs_server::{FileStore, GameStore, MemoryStore};
+
+/// Send a request to `app` without a server, returning the status and the
+/// JSON answered.
+async fn request(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    let body = match body {
+        Some(body) => Body::from(body.to_string()),
+        None => Body::empty(),
+    };
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+fn app(store: impl GameStore + 'static) -> Router {
+    sweeprs_server::app(Arc::new(store))
+}
+
+#[tokio::test]
+async fn play_a_game() {
+    let app = app(MemoryStore::default());
+    let (status, game) = request(&app, "POST", "/games", Some(json!({"seed": 3}))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(game["state"], "new");
+    assert_eq!(game["mines_left"], 10);
+    assert_eq!(game["rows"][0], "#########");
+    let uri = format!("/games/{}", game["id"].as_str().unwrap());
+
+    let cell = json!({"row": 4, "column": 4});
+    let (status, opened) =
+        request(&app, "POST", &format!("{}/open", uri), Some(cell.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(opened["state"], "playing");
+    assert_eq!(
+        request(&app, "GET", &uri, None).await,
+        (StatusCode::OK, opened.clone())
+    );
+
+    // the same seed and first move give the same board
+    let (_, other) = request(&app, "POST", "/games", Some(json!({"seed": 3}))).await;
+    let other_uri = format!("/games/{}/open", other["id"].as_str().unwrap());
+    let (_, other) = request(&app, "POST", &other_uri, Some(cell.clone())).await;
+    assert_eq!(other["rows"], opened["rows"]);
+
+    let (status, refused) = request(&app, "POST", &format!("{}/open", uri), Some(cell)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(refused["error"], "cell is already open");
+}
+
+#[tokio::test]
+async fn errors() {
+    let app = app(MemoryStore::default());
+    let (status, error) = request(&app, "POST", "/games", Some(json!({"mines": 81}))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["error"], "board configuration is invalid");
+    let huge = json!({"height": 100_000, "width": 100_000});
+    let (status, error) = request(&app, "POST", "/games", Some(huge)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["error"], "board has more than 65536 cells");
+    let (status, error) = request(&app, "POST", "/games", Some(json!({"mines": "ten"}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("mines"));
+
+    let (_, game) = request(&app, "POST", "/games", Some(json!({}))).await;
+    let uri = format!("/games/{}", game["id"].as_str().unwrap());
+    let outside = json!({"row": 9, "column": 0});
+    let (status, error) = request(&app, "POST", &format!("{}/flag", uri), Some(outside)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "index is out of bound");
+    let inside = json!({"row": 0, "column": 0});
+    let (status, error) =
+        request(&app, "POST", &format!("{}/jump", uri), Some(inside.clone())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("jump"));
+    let (status, error) = request(
+        &app,
+        "POST",
+        &format!("{}/open", uri),
+        Some(json!({"row": 0})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"].as_str().unwrap().contains("column"));
+    let (status, error) = request(&app, "POST", &format!("{}/chord", uri), Some(inside)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(error["error"], "cell is not open");
+
+    let (status, error) = request(&app, "GET", "/games/0000", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["error"], "no such game");
+}
+
+#[tokio::test]
+async fn saved_games() {
+    let dir = std::env::temp_dir().join(format!("sweeprs-rest-{}", std::process::id()));
+    let before = app(FileStore::new(&dir).unwrap());
+    let (_, game) = request(&before, "POST", "/games", Some(json!({"seed": 1}))).await;
+    let uri = format!("/games/{}", game["id"].as_str().unwrap());
+    let cell = json!({"row": 0, "column": 0});
+    let (_, flagged) = request(&before, "POST", &format!("{}/flag", uri), Some(cell)).await;
+    assert_eq!(flagged["mines_left"], 9);
+
+    // a restarted server still has the game
+    let after = app(FileStore::new(&dir).unwrap());
+    assert_eq!(
+        request(&after, "GET", &uri, None).await,
+        (StatusCode::OK, flagged)
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
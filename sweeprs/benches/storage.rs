@@ -0,0 +1,95 @@
+//! Compare the nested `Board` against the bit-packed `PackedBoard`.
+//!
+//! Run with `cargo bench -p sweeprs --bench storage`, optionally followed
+//! by the board sizes to measure, e.g. `-- 1000 10000`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use sweeprs::{Board, Cell, CellKind, PackedBoard, Pos, SweeperBoard};
+
+/// Keep track of live heap bytes so the footprint of each layout can be
+/// measured directly instead of estimated.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+struct Report {
+    memory: usize,
+    cascade: Duration,
+    opened: usize,
+    open: Duration,
+}
+
+fn measure<B: SweeperBoard<Cell = Cell>>(size: usize) -> Report {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let mut board = B::new(size, size, size * size / 100).unwrap();
+    let start = Instant::now();
+    board.open(Pos::new(size / 2, size / 2));
+    let cascade = start.elapsed();
+    let memory = ALLOCATED.load(Ordering::Relaxed) - before;
+
+    // the mine layout is read upfront so only the opening is timed
+    let safe: Vec<Pos> = board
+        .iter()
+        .filter(|(_, cell)| matches!(cell.kind, CellKind::Free))
+        .map(|(pos, _)| pos)
+        .collect();
+    let start = Instant::now();
+    for &pos in &safe {
+        board.open(pos);
+    }
+    Report {
+        memory,
+        cascade,
+        opened: safe.len(),
+        open: start.elapsed(),
+    }
+}
+
+fn print(name: &str, report: Report) {
+    println!(
+        "  {:<12} memory {:>12} B   first click cascade {:>10.2?}   open {} cells {:>10.2?} ({:.1} Mcells/s)",
+        name,
+        report.memory,
+        report.cascade,
+        report.opened,
+        report.open,
+        report.opened as f64 / report.open.as_secs_f64() / 1e6,
+    );
+}
+
+fn main() {
+    let sizes: Vec<usize> = std::env::args()
+        .skip(1)
+        .filter_map(|arg| arg.parse().ok())
+        .collect();
+    let sizes = if sizes.is_empty() {
+        vec![100, 1000, 3000]
+    } else {
+        sizes
+    };
+    for size in sizes {
+        println!("{}x{} board with 1% mines", size, size);
+        print("Board", measure::<Board>(size));
+        print("PackedBoard", measure::<PackedBoard>(size));
+    }
+}
@@ -1,56 +1,336 @@
-use std::vec;
+use std::{collections::HashMap, ops::Index, vec};
 
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 
 use crate::{
     cell::{Cell, CellKind, CellState, SweeperCell},
     error::Error,
+    pos::{Neighbours, Pos},
+    reveal::{chord, reveal, Grid},
+    rules::{CellView, Move, Refused},
 };
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum BoardResult {
     Win,
     Lost,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum BoardState {
     Uninitialized,
     Playing,
     Finished(BoardResult),
 }
 
-pub trait SweeperBoard<T>
+/// Minesweeper engine. Implementors store the cells and handle the moves,
+/// everything that can be derived from the dimension and the cells is
+/// provided on top of that.
+pub trait SweeperBoard
 where
     Self: Sized,
-    T: SweeperCell,
 {
+    type Cell: SweeperCell;
+
     fn new(height: usize, width: usize, mine_count: usize) -> Result<Self, Error>;
 
-    fn open(&mut self, i: usize, j: usize) -> &CellKind;
+    /// Same as `new`, but the mines are placed from `seed`, so the same
+    /// seed and first move always give the same board.
+    fn with_seed(height: usize, width: usize, mine_count: usize, seed: u64) -> Result<Self, Error>;
 
-    fn open_save(&mut self, i: usize, j: usize) -> Result<&CellKind, Error>;
+    fn height(&self) -> usize;
 
-    fn flag(&mut self, i: usize, j: usize) -> &CellState;
+    fn width(&self) -> usize;
 
-    fn flag_save(&mut self, i: usize, j: usize) -> Result<&CellState, Error>;
+    /// Number of mines on the whole board.
+    fn mine_count(&self) -> usize;
 
-    fn state(&self) -> &BoardState;
+    /// Number of flagged cells.
+    fn flag_count(&self) -> usize;
+
+    /// Number of cells not opened yet, flagged ones included.
+    fn closed_count(&self) -> usize {
+        self.iter()
+            .filter(|(_, cell)| !matches!(cell.state(), CellState::Opened))
+            .count()
+    }
+
+    /// Mines left to flag, negative when there are more flags than mines.
+    fn remaining_flags(&self) -> isize {
+        self.mine_count() as isize - self.flag_count() as isize
+    }
+
+    /// Panics if `pos` is out of the board.
+    fn cell(&self, pos: Pos) -> &Self::Cell;
+
+    fn count_adjacent_mines(&self, pos: Pos) -> usize;
+
+    fn count_adjacent_flags(&self, pos: Pos) -> usize {
+        self.neighbours(pos)
+            .filter(|&nbr| matches!(self.cell(nbr).state(), CellState::Flagged))
+            .count()
+    }
+
+    /// Iterate over the positions surrounding `pos`.
+    fn neighbours(&self, pos: Pos) -> Neighbours {
+        pos.neighbours(self.height(), self.width())
+    }
+
+    /// Iterate over every cell along with its position, row by row.
+    fn iter(&self) -> impl Iterator<Item = (Pos, &Self::Cell)> {
+        let width = self.width();
+        (0..self.height() * width).map(move |idx| {
+            let pos = Pos::new(idx / width, idx % width);
+            (pos, self.cell(pos))
+        })
+    }
+
+    /// Iterate over the closed cells that are adjacent to at least one
+    /// opened cell, row by row.
+    fn frontier(&self) -> impl Iterator<Item = Pos> {
+        self.iter()
+            .filter(|(_, cell)| matches!(cell.state(), CellState::Closed))
+            .filter(move |&(pos, _)| {
+                self.neighbours(pos)
+                    .any(|nbr| matches!(self.cell(nbr).state(), CellState::Opened))
+            })
+            .map(|(pos, _)| pos)
+    }
+
+    /// Minimum number of clicks needed to clear the board, known as 3BV.
+    /// Every empty area counts as one click, and so does every numbered
+    /// cell that is not revealed by opening an empty area. Zero until the
+    /// mines are placed.
+    fn three_bv(&self) -> usize {
+        if let BoardState::Uninitialized = self.state() {
+            return 0;
+        }
+        let is_empty = |pos: Pos| {
+            matches!(self.cell(pos).kind(), CellKind::Free) && self.count_adjacent_mines(pos) == 0
+        };
+        let mut visited = vec![false; self.height() * self.width()];
+        let mut clicks = 0;
+        for (pos, _) in self.iter() {
+            if visited[pos.i * self.width() + pos.j] || !is_empty(pos) {
+                continue;
+            }
+            clicks += 1;
+            visited[pos.i * self.width() + pos.j] = true;
+            let mut stack = vec![pos];
+            while let Some(pos) = stack.pop() {
+                for nbr in self.neighbours(pos) {
+                    let idx = nbr.i * self.width() + nbr.j;
+                    if !visited[idx] {
+                        visited[idx] = true;
+                        if is_empty(nbr) {
+                            stack.push(nbr);
+                        }
+                    }
+                }
+            }
+        }
+        clicks
+            + self
+                .iter()
+                .filter(|(pos, cell)| {
+                    matches!(cell.kind(), CellKind::Free) && !visited[pos.i * self.width() + pos.j]
+                })
+                .count()
+    }
 
-    fn cells(&self) -> &Vec<Vec<T>>;
+    fn open(&mut self, pos: Pos) -> &CellKind;
+
+    /// Same as `open`, but return an error instead of panicking when the
+    /// index is out of bound.
+    fn open_save(&mut self, pos: Pos) -> Result<&CellKind, Error> {
+        if pos.i < self.height() && pos.j < self.width() {
+            Ok(self.open(pos))
+        } else {
+            Err(Error::IndexOutOfBoundError)
+        }
+    }
+
+    fn flag(&mut self, pos: Pos) -> &CellState;
+
+    /// Same as `flag`, but return an error instead of panicking when the
+    /// index is out of bound.
+    fn flag_save(&mut self, pos: Pos) -> Result<&CellState, Error> {
+        if pos.i < self.height() && pos.j < self.width() {
+            Ok(self.flag(pos))
+        } else {
+            Err(Error::IndexOutOfBoundError)
+        }
+    }
+
+    /// Play `action` at `pos` when the rules allow it, see `Move::check`.
+    /// Nothing is played once the game is over. Unlike `open` and `flag`,
+    /// opening an open cell does not chord.
+    fn play(&mut self, action: Move, pos: Pos) -> Result<(), Refused> {
+        if pos.i >= self.height() || pos.j >= self.width() {
+            return Err(Refused::OutOfBound);
+        }
+        if let BoardState::Finished(_) = self.state() {
+            return Err(Refused::GameOver);
+        }
+        action.check(self.cell(pos).state())?;
+        match action {
+            Move::Flag => {
+                self.flag(pos);
+            }
+            Move::Open | Move::Chord => {
+                self.open(pos);
+            }
+        }
+        Ok(())
+    }
+
+    /// The cell at `pos` as its player may see it, mines are only shown
+    /// once they are opened or the game is over. Panics if `pos` is out of
+    /// the board.
+    fn view(&self, pos: Pos) -> CellView {
+        CellView::of(self, pos)
+    }
+
+    /// Close every cell and remove the flags, keeping the mines where
+    /// they are, to play the same layout again. A board without mines yet
+    /// is left as is.
+    fn restart(&mut self);
+
+    fn state(&self) -> &BoardState;
 }
 
 /// Default implementation of the SweeperBoard trait
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(try_from = "SavedBoard")
+)]
 pub struct Board {
     cells: Vec<Vec<Cell>>,
     mine_count: usize,
+    flag_count: usize,
     state: BoardState,
     closed_cell_count: usize,
+    seed: Option<u64>,
+}
+
+/// A `Board` as it is saved, only turned into one once its cells and
+/// counts agree with each other and with `Board::new`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SavedBoard {
+    cells: Vec<Vec<Cell>>,
+    mine_count: usize,
+    flag_count: usize,
+    state: BoardState,
+    closed_cell_count: usize,
+    seed: Option<u64>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedBoard> for Board {
+    type Error = Error;
+
+    fn try_from(saved: SavedBoard) -> Result<Self, Error> {
+        let height = saved.cells.len();
+        let width = saved.cells.first().map_or(0, Vec::len);
+        if height < 9 || width < 9 || saved.cells.iter().any(|row| row.len() != width) {
+            return Err(Error::InvalidConfigError);
+        }
+        let cells = || saved.cells.iter().flatten();
+        let count = |f: fn(&Cell) -> bool| cells().filter(|&cell| f(cell)).count();
+        let mines = match saved.state {
+            // nothing is open and the mines are still to be placed
+            BoardState::Uninitialized => {
+                let placed = count(|cell| {
+                    !matches!(cell.kind, CellKind::Uninitialized)
+                        || matches!(cell.state, CellState::Opened)
+                });
+                match placed {
+                    0 if saved.mine_count <= height * width - 9 => saved.mine_count,
+                    _ => return Err(Error::InvalidConfigError),
+                }
+            }
+            _ if count(|cell| matches!(cell.kind, CellKind::Uninitialized)) > 0 => {
+                return Err(Error::InvalidConfigError)
+            }
+            _ => count(|cell| matches!(cell.kind, CellKind::Mine)),
+        };
+        if mines != saved.mine_count
+            || count(|cell| matches!(cell.state, CellState::Flagged)) != saved.flag_count
+            || count(|cell| !matches!(cell.state, CellState::Opened)) != saved.closed_cell_count
+        {
+            return Err(Error::InvalidConfigError);
+        }
+        Ok(Self {
+            cells: saved.cells,
+            mine_count: saved.mine_count,
+            flag_count: saved.flag_count,
+            state: saved.state,
+            closed_cell_count: saved.closed_cell_count,
+            seed: saved.seed,
+        })
+    }
+}
+
+/// Random source the mines are placed from, seeded when a seed is given.
+#[cfg(feature = "thread_rng")]
+pub(crate) fn mine_rng(seed: Option<u64>) -> Box<dyn RngCore> {
+    match seed {
+        Some(seed) => Box::new(StdRng::seed_from_u64(seed)),
+        None => Box::new(rand::thread_rng()),
+    }
+}
+
+/// Without the OS to seed from, unseeded boards take the keys the standard
+/// library hashes with. They are only random on platforms that have an OS
+/// source, elsewhere pass a seed.
+#[cfg(not(feature = "thread_rng"))]
+pub(crate) fn mine_rng(seed: Option<u64>) -> Box<dyn RngCore> {
+    use std::{collections::hash_map::RandomState, hash::BuildHasher};
+
+    let seed = seed.unwrap_or_else(|| RandomState::new().hash_one(0));
+    Box::new(StdRng::seed_from_u64(seed))
+}
+
+/// Pick `mine_count` cells of a board of `cell_count` cells, leaving out
+/// the `safe` ones (sorted), uniformly at random. This is a partial
+/// Fisher-Yates shuffle of the eligible cells that only remembers the
+/// slots it swapped, so the memory taken is bounded by the number of
+/// mines instead of the number of cells. It draws the same numbers as
+/// `SliceRandom::partial_shuffle`, which seeded boards were built with.
+pub(crate) fn place_mines<R: Rng + ?Sized>(
+    cell_count: usize,
+    safe: &[usize],
+    mine_count: usize,
+    rng: &mut R,
+) -> Vec<usize> {
+    let eligible = cell_count - safe.len();
+    let mut swapped: HashMap<usize, usize> = HashMap::new();
+    (eligible.saturating_sub(mine_count)..eligible)
+        .rev()
+        .map(|slot| {
+            let other = if slot < u32::MAX as usize {
+                rng.gen_range(0..slot as u32 + 1) as usize
+            } else {
+                rng.gen_range(0..slot + 1)
+            };
+            let picked = swapped.get(&other).copied().unwrap_or(other);
+            // `slot` is never drawn again, only `other` has to remember
+            // what was moved into it
+            swapped.insert(other, swapped.get(&slot).copied().unwrap_or(slot));
+            safe.iter()
+                .fold(picked, |idx, &safe| if safe <= idx { idx + 1 } else { idx })
+        })
+        .collect()
 }
 
 macro_rules! count_board_stat {
     ($visibility:vis, $func_name:ident, $expected:pat, $field:ident) => {
-        $visibility fn $func_name(&self, i: usize, j: usize) -> usize {
+        $visibility fn $func_name(&self, pos: Pos) -> usize {
             let mut count = 0;
-            for (i_nbr, j_nbr) in self.nbr_indices(i, j) {
+            for (i_nbr, j_nbr) in self.nbr_indices(pos.i, pos.j) {
                 if let $expected = self.cells[i_nbr][j_nbr].$field {
                     count += 1;
                 }
@@ -70,15 +350,22 @@ impl Board {
         for (i_nbr, j_nbr) in self.nbr_indices(i, j) {
             self.cells[i_nbr][j_nbr].kind = CellKind::Free;
         }
-        let mut placed_mine = 0;
-        let mut rng = rand::thread_rng();
-        while placed_mine < self.mine_count {
-            let i = rng.gen_range(0..self.height());
-            let j = rng.gen_range(0..self.width());
-            if let CellKind::Uninitialized = self.cells[i][j].kind {
-                self.cells[i][j].kind = CellKind::Mine;
-                placed_mine += 1;
-            }
+        let width = self.width();
+        let mut safe: Vec<usize> = self
+            .nbr_indices(i, j)
+            .into_iter()
+            .map(|(i, j)| i * width + j)
+            .collect();
+        safe.push(i * width + j);
+        safe.sort_unstable();
+        let mines = place_mines(
+            self.height() * width,
+            &safe,
+            self.mine_count,
+            &mut *mine_rng(self.seed),
+        );
+        for idx in mines {
+            self.cells[idx / width][idx % width].kind = CellKind::Mine;
         }
         self.state = BoardState::Playing;
         self.cells.iter_mut().flatten().for_each(|cell| {
@@ -91,107 +378,121 @@ impl Board {
     /// Returs an array of tupple containing the index of neighboring
     /// cells starting from left to right, top to bottom.
     fn nbr_indices(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
-        let mut indices: Vec<(usize, usize)> = Vec::new();
-        for i_offset in 0..3 {
-            for j_offset in 0..3 {
-                let i_nbr = i + i_offset;
-                let j_nbr = j + j_offset;
-                if 0 < i_nbr
-                    && i_nbr <= self.height()
-                    && 0 < j_nbr
-                    && j_nbr <= self.width()
-                    && (i_nbr, j_nbr) != (i + 1, j + 1)
-                {
-                    indices.push((i_nbr - 1, j_nbr - 1));
-                }
-            }
-        }
-        indices
+        self.neighbours(Pos::new(i, j)).map(Into::into).collect()
     }
 
-    /// A convenient alias from `self.cells.len()`.
-    pub fn height(&self) -> usize {
-        self.cells.len()
-    }
-
-    /// A convenient alias from `self.cells[0].len()`. Guarateed to
-    /// return because the height of the board is never less than 9.
-    pub fn width(&self) -> usize {
-        self.cells[0].len()
+    /// Build an initialized board out of existing cells, used to compare
+    /// other implementations against this one.
+    #[cfg(test)]
+    pub(crate) fn from_cells(cells: Vec<Vec<Cell>>, mine_count: usize) -> Self {
+        let closed_cell_count = cells
+            .iter()
+            .flatten()
+            .filter(|cell| !matches!(cell.state, CellState::Opened))
+            .count();
+        let flag_count = cells
+            .iter()
+            .flatten()
+            .filter(|cell| matches!(cell.state, CellState::Flagged))
+            .count();
+        Self {
+            cells,
+            mine_count,
+            flag_count,
+            state: BoardState::Playing,
+            closed_cell_count,
+            seed: None,
+        }
     }
-
-    count_board_stat!(pub, count_adjacent_mines, CellKind::Mine, kind);
-    count_board_stat!(, count_surrounding_flags, CellState::Flagged, state);
 }
 
-macro_rules! save_op {
-    ($func_name:ident, $op:ident, $return_type:ident) => {
-        fn $func_name(&mut self, i: usize, j: usize) -> Result<&$return_type, Error> {
-            if i < self.height() && j < self.width() {
-                Ok(self.$op(i, j))
-            } else {
-                Err(Error::IndexOutOfBoundError)
-            }
-        }
-    };
-}
+impl SweeperBoard for Board {
+    type Cell = Cell;
 
-impl SweeperBoard<Cell> for Board {
     /// Create a new minesweeper board. `height` and `width` cannot be under 9,
     /// while `mine_count` cannot exceed `height * width - 9` since the initial
     /// cell and its neighbors must be a free cell. Return error if given invalid
-    /// configuration.
+    /// configuration, or when the rows or the cells of a row cannot be
+    /// allocated: one is over `isize::MAX` bytes or the allocator refused it.
     fn new(height: usize, width: usize, mine_count: usize) -> Result<Self, Error> {
-        if width < 9 || height < 9 || height * width - 9 < mine_count {
+        let cells = height.checked_mul(width).ok_or(Error::InvalidConfigError)?;
+        if width < 9 || height < 9 || cells - 9 < mine_count {
             return Err(Error::InvalidConfigError);
         }
         let cell = Cell {
             kind: CellKind::Uninitialized,
             state: CellState::Closed,
         };
+        let mut rows = Vec::new();
+        rows.try_reserve_exact(height)
+            .map_err(|_| Error::InvalidConfigError)?;
+        for _ in 0..height {
+            let mut row = Vec::new();
+            row.try_reserve_exact(width)
+                .map_err(|_| Error::InvalidConfigError)?;
+            row.resize(width, cell.clone());
+            rows.push(row);
+        }
         Ok(Self {
-            cells: vec![vec![cell; width]; height],
+            cells: rows,
             mine_count,
+            flag_count: 0,
             state: BoardState::Uninitialized,
-            closed_cell_count: width * height,
+            closed_cell_count: cells,
+            seed: None,
         })
     }
 
+    fn with_seed(height: usize, width: usize, mine_count: usize, seed: u64) -> Result<Self, Error> {
+        let mut board = Self::new(height, width, mine_count)?;
+        board.seed = Some(seed);
+        Ok(board)
+    }
+
+    /// A convenient alias from `self.cells.len()`.
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// A convenient alias from `self.cells[0].len()`. Guarateed to
+    /// return because the height of the board is never less than 9.
+    fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    fn mine_count(&self) -> usize {
+        self.mine_count
+    }
+
+    fn flag_count(&self) -> usize {
+        self.flag_count
+    }
+
+    fn closed_count(&self) -> usize {
+        self.closed_cell_count
+    }
+
+    fn cell(&self, pos: Pos) -> &Cell {
+        &self.cells[pos.i][pos.j]
+    }
+
+    count_board_stat!(, count_adjacent_mines, CellKind::Mine, kind);
+    count_board_stat!(, count_adjacent_flags, CellState::Flagged, state);
+
     /// Open a cell, propagate if all neighboring cell is a free cell.
     /// Opening an opened cell will propagate if flagged cell count is
     /// equal to surrounding mine count.
     ///
     /// Propagation is stopped when propagation reached a mine cell.
-    fn open(&mut self, i: usize, j: usize) -> &CellKind {
+    fn open(&mut self, pos: Pos) -> &CellKind {
+        let Pos { i, j } = pos;
         if let BoardState::Uninitialized = self.state {
             self.initialize(i, j);
         }
         match self.cells[i][j].state {
-            CellState::Closed => {
-                self.cells[i][j].state = CellState::Opened;
-                self.closed_cell_count -= 1;
-                if let CellKind::Mine = self.cells[i][j].kind {
-                    self.state = BoardState::Finished(BoardResult::Lost);
-                }
-                if let BoardState::Playing = self.state {
-                    if self.count_adjacent_mines(i, j) == 0 {
-                        for (i_nbr, j_nbr) in self.nbr_indices(i, j) {
-                            self.open(i_nbr, j_nbr);
-                        }
-                    }
-                }
-            }
-            CellState::Opened => {
-                let mine_count = self.count_adjacent_mines(i, j);
-                if mine_count > 0 && self.count_surrounding_flags(i, j) == mine_count {
-                    for (i_nbr, j_nbr) in self.nbr_indices(i, j) {
-                        if let CellState::Closed = self.cells[i_nbr][j_nbr].state {
-                            self.open(i_nbr, j_nbr);
-                        }
-                    }
-                }
-            }
-            _ => (),
+            CellState::Closed => reveal(self, pos),
+            CellState::Opened => chord(self, pos),
+            CellState::Flagged => (),
         }
         if let BoardState::Playing = self.state {
             if self.mine_count == self.closed_cell_count {
@@ -201,23 +502,74 @@ impl SweeperBoard<Cell> for Board {
         &self.cells[i][j].kind
     }
 
-    save_op!(open_save, open, CellKind);
-
     /// Flag a cell. Flagged cell cannot be opened until unflagged.
     /// Remove the flag by flagging a flagged cell again. Flagged cell
     /// counts toward opening an opened cell propagation.
-    fn flag(&mut self, i: usize, j: usize) -> &CellState {
-        self.cells[i][j].flag()
+    fn flag(&mut self, pos: Pos) -> &CellState {
+        let Pos { i, j } = pos;
+        match self.cells[i][j].flag() {
+            CellState::Flagged => self.flag_count += 1,
+            CellState::Closed => self.flag_count -= 1,
+            CellState::Opened => (),
+        }
+        &self.cells[i][j].state
     }
 
-    save_op!(flag_save, flag, CellState);
+    fn restart(&mut self) {
+        if let BoardState::Uninitialized = self.state {
+            return;
+        }
+        for cell in self.cells.iter_mut().flatten() {
+            cell.state = CellState::Closed;
+        }
+        self.flag_count = 0;
+        self.closed_cell_count = self.height() * self.width();
+        self.state = BoardState::Playing;
+    }
 
     fn state(&self) -> &BoardState {
         &self.state
     }
+}
+
+impl Grid for Board {
+    type Coord = Pos;
+
+    fn is_closed(&self, pos: Pos) -> bool {
+        matches!(self[pos].state, CellState::Closed)
+    }
+
+    fn is_flagged(&self, pos: Pos) -> bool {
+        matches!(self[pos].state, CellState::Flagged)
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(self.state, BoardState::Playing)
+    }
 
-    fn cells(&self) -> &Vec<Vec<Cell>> {
-        &self.cells
+    fn mines_around(&self, pos: Pos) -> usize {
+        self.count_adjacent_mines(pos)
+    }
+
+    fn nbrs(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        self.neighbours(pos)
+    }
+
+    fn open_cell(&mut self, pos: Pos) {
+        let cell = &mut self.cells[pos.i][pos.j];
+        cell.state = CellState::Opened;
+        self.closed_cell_count -= 1;
+        if let CellKind::Mine = cell.kind {
+            self.state = BoardState::Finished(BoardResult::Lost);
+        }
+    }
+}
+
+impl Index<Pos> for Board {
+    type Output = Cell;
+
+    fn index(&self, pos: Pos) -> &Cell {
+        self.cell(pos)
     }
 }
 
@@ -228,7 +580,7 @@ mod tests {
     #[test]
     fn open_cell() {
         let mut board = Board::new(9, 9, 10).unwrap();
-        board.open(4, 4);
+        board.open(Pos::new(4, 4));
 
         // check surrounding initial
         for (i_nbr, j_nbr) in board.nbr_indices(4, 4) {
@@ -248,7 +600,7 @@ mod tests {
         let mut board = Board::new(9, 9, 10).unwrap();
         let mut mine_count = 0;
         let mut uninitialized_cell = 0;
-        for cell in board.cells().iter().flatten() {
+        for cell in board.cells.iter().flatten() {
             match cell.kind {
                 CellKind::Mine => mine_count += 1,
                 CellKind::Uninitialized => uninitialized_cell += 1,
@@ -260,7 +612,7 @@ mod tests {
         board.initialize(4, 4);
         mine_count = 0;
         uninitialized_cell = 0;
-        for cell in board.cells().iter().flatten() {
+        for cell in board.cells.iter().flatten() {
             match cell.kind {
                 CellKind::Mine => mine_count += 1,
                 CellKind::Uninitialized => uninitialized_cell += 1,
@@ -271,6 +623,175 @@ mod tests {
         assert_eq!(uninitialized_cell, 0);
     }
 
+    #[test]
+    fn dense_board() {
+        let mut board = Board::new(9, 9, 72).unwrap();
+        board.initialize(4, 4);
+        for (i, row) in board.cells.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                let is_safe = (3..=5).contains(&i) && (3..=5).contains(&j);
+                assert_eq!(matches!(cell.kind, CellKind::Mine), !is_safe);
+            }
+        }
+    }
+
+    macro_rules! uniform_placement_test {
+        ($func_name:ident, $i:expr, $j:expr, $mine_count:expr) => {
+            #[test]
+            fn $func_name() {
+                const TRIALS: usize = 20_000;
+                let mut hits = [[0usize; 9]; 9];
+                for _ in 0..TRIALS {
+                    let mut board = Board::new(9, 9, $mine_count).unwrap();
+                    board.initialize($i, $j);
+                    for (i, row) in board.cells.iter().enumerate() {
+                        for (j, cell) in row.iter().enumerate() {
+                            if let CellKind::Mine = cell.kind {
+                                hits[i][j] += 1;
+                            }
+                        }
+                    }
+                }
+                let safe = board_safe_zone($i, $j);
+                let eligible = 81 - safe.len();
+                let p = $mine_count as f64 / eligible as f64;
+                let expected = TRIALS as f64 * p;
+                let std_dev = (TRIALS as f64 * p * (1.0 - p)).sqrt();
+                let mut chi_square = 0.0;
+                for (i, row) in hits.iter().enumerate() {
+                    for (j, &count) in row.iter().enumerate() {
+                        if safe.contains(&(i, j)) {
+                            assert_eq!(count, 0);
+                            continue;
+                        }
+                        // every eligible cell should be within 5 standard
+                        // deviations of the expected hit count
+                        let deviation = (count as f64 - expected).abs();
+                        assert!(
+                            deviation < 5.0 * std_dev,
+                            "cell ({}, {}) hit {} times",
+                            i,
+                            j,
+                            count
+                        );
+                        chi_square += (count as f64 - expected).powi(2) / expected;
+                    }
+                }
+                // mean of the statistic is roughly the number of eligible
+                // cells, anything far above that means a biased placement
+                assert!(
+                    chi_square < 2.0 * eligible as f64,
+                    "chi square {}",
+                    chi_square
+                );
+            }
+        };
+    }
+
+    fn board_safe_zone(i: usize, j: usize) -> Vec<(usize, usize)> {
+        let board = Board::new(9, 9, 10).unwrap();
+        let mut safe = board.nbr_indices(i, j);
+        safe.push((i, j));
+        safe
+    }
+
+    uniform_placement_test!(uniform_center_sparse, 4, 4, 10);
+    uniform_placement_test!(uniform_center_dense, 4, 4, 60);
+    uniform_placement_test!(uniform_corner, 0, 0, 30);
+    uniform_placement_test!(uniform_edge, 0, 4, 40);
+
+    #[test]
+    fn iterate() {
+        let mut board = Board::new(9, 9, 10).unwrap();
+        board.open(Pos::new(4, 4));
+        assert_eq!(board.iter().count(), 81);
+        for (pos, cell) in board.iter() {
+            assert!(std::ptr::eq(cell, &board[pos]));
+            assert!(std::ptr::eq(cell, &board.cells[pos.i][pos.j]));
+        }
+        let frontier: Vec<Pos> = board.frontier().collect();
+        assert!(!frontier.is_empty());
+        for (pos, cell) in board.iter() {
+            let touches_opened = board
+                .neighbours(pos)
+                .any(|nbr| matches!(board[nbr].state, CellState::Opened));
+            let expected = matches!(cell.state, CellState::Closed) && touches_opened;
+            assert_eq!(frontier.contains(&pos), expected);
+        }
+    }
+
+    #[test]
+    fn flag_count() {
+        let mut board = Board::new(9, 9, 10).unwrap();
+        board.flag(Pos::new(0, 0));
+        board.flag(Pos::new(0, 1));
+        assert_eq!(board.flag_count(), 2);
+        assert_eq!(board.remaining_flags(), 8);
+        board.flag(Pos::new(0, 0));
+        assert_eq!(board.flag_count(), 1);
+        assert_eq!(board.count_adjacent_flags(Pos::new(1, 1)), 1);
+        board.open(Pos::new(4, 4));
+        board.flag(Pos::new(4, 4));
+        assert_eq!(board.flag_count(), 1);
+    }
+
+    /// Build a 9x9 board from a picture, `*` being a mine.
+    fn from_layout(layout: [&str; 9]) -> Board {
+        let cells = layout
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| Cell {
+                        kind: if c == '*' {
+                            CellKind::Mine
+                        } else {
+                            CellKind::Free
+                        },
+                        state: CellState::Closed,
+                    })
+                    .collect()
+            })
+            .collect::<Vec<Vec<Cell>>>();
+        let mine_count = layout
+            .iter()
+            .flat_map(|row| row.chars())
+            .filter(|&c| c == '*')
+            .count();
+        Board::from_cells(cells, mine_count)
+    }
+
+    #[test]
+    fn three_bv() {
+        assert_eq!(Board::new(9, 9, 10).unwrap().three_bv(), 0);
+        // one opening covers everything
+        let board = from_layout([
+            "........*",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+        ]);
+        assert_eq!(board.three_bv(), 1);
+        // both corners are walled off by mines, leaving two numbers that
+        // need their own click
+        let board = from_layout([
+            ".*.......",
+            "**.......",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".........",
+            ".......**",
+            ".......*.",
+        ]);
+        assert_eq!(board.three_bv(), 1 + 2);
+    }
+
     #[test]
     fn dimension() {
         let board = Board::new(9, 9, 10).unwrap();
@@ -281,10 +802,55 @@ mod tests {
     #[test]
     fn out_of_bound() {
         let mut board = Board::new(9, 9, 10).unwrap();
-        assert!(board.open_save(10, 0).is_err());
-        assert!(board.open_save(0, 10).is_err());
-        assert!(board.flag_save(10, 0).is_err());
-        assert!(board.flag_save(0, 10).is_err());
+        assert!(board.open_save(Pos::new(10, 0)).is_err());
+        assert!(board.open_save(Pos::new(0, 10)).is_err());
+        assert!(board.flag_save(Pos::new(10, 0)).is_err());
+        assert!(board.flag_save(Pos::new(0, 10)).is_err());
+    }
+
+    #[test]
+    fn huge_cascade() {
+        // a recursive flood fill would blow the stack here
+        let mut board = Board::new(300, 300, 0).unwrap();
+        board.open(Pos::new(150, 150));
+        assert!(matches!(
+            board.state(),
+            BoardState::Finished(BoardResult::Win)
+        ));
+    }
+
+    #[test]
+    fn restart() {
+        let mut board = Board::new(9, 9, 10).unwrap();
+        board.restart();
+        assert!(matches!(board.state(), BoardState::Uninitialized));
+        board.open(Pos::new(4, 4));
+        let mines: Vec<Pos> = board
+            .iter()
+            .filter(|(_, cell)| matches!(cell.kind, CellKind::Mine))
+            .map(|(pos, _)| pos)
+            .collect();
+        board.flag(mines[0]);
+        board.open(mines[1]);
+        assert!(matches!(board.state(), BoardState::Finished(_)));
+        board.restart();
+        assert!(matches!(board.state(), BoardState::Playing));
+        assert_eq!(board.flag_count(), 0);
+        assert!(board
+            .iter()
+            .all(|(_, cell)| matches!(cell.state, CellState::Closed)));
+        let after: Vec<Pos> = board
+            .iter()
+            .filter(|(_, cell)| matches!(cell.kind, CellKind::Mine))
+            .map(|(pos, _)| pos)
+            .collect();
+        assert_eq!(after, mines);
+        // the first move is no longer protected, the layout is known
+        board.open(mines[0]);
+        assert!(matches!(
+            board.state(),
+            BoardState::Finished(BoardResult::Lost)
+        ));
     }
 
     #[test]
@@ -297,6 +863,13 @@ mod tests {
         assert!(invalid_width.is_err());
         let too_many_mines = Board::new(9, 9, 73);
         assert!(too_many_mines.is_err());
+        assert!(Board::new(usize::MAX, 9, 0).is_err());
+        // more rows than fit in `isize::MAX` bytes, then a row too long
+        let rows = isize::MAX as usize / std::mem::size_of::<Vec<Cell>>() + 1;
+        assert!(Board::new(rows, 9, 0).is_err());
+        assert!(Board::new(usize::MAX / 9, 9, 0).is_err());
+        let columns = isize::MAX as usize / std::mem::size_of::<Cell>() + 1;
+        assert!(Board::new(9, columns, 0).is_err());
     }
 
     macro_rules! nbr_indices_test {
@@ -318,4 +891,92 @@ mod tests {
     nbr_indices_test!(left_nbr, 4, 0, [(3, 0), (3, 1), (4, 1), (5, 0), (5, 1),]);
     nbr_indices_test!(right_nbr, 4, 8, [(3, 7), (3, 8), (4, 7), (5, 7), (5, 8),]);
     nbr_indices_test!(bot_nbr, 8, 4, [(7, 3), (7, 4), (7, 5), (8, 3), (8, 5),]);
+
+    #[test]
+    fn seeded() {
+        let mines = |seed| {
+            let mut board = Board::with_seed(16, 16, 40, seed).unwrap();
+            board.open(Pos::new(8, 8));
+            board
+                .iter()
+                .filter(|(_, cell)| matches!(cell.kind, CellKind::Mine))
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(mines(7), mines(7));
+        assert_ne!(mines(7), mines(8));
+    }
+
+    #[test]
+    fn closed_count() {
+        let mut board = Board::with_seed(9, 9, 10, 3).unwrap();
+        assert_eq!(board.closed_count(), 81);
+        board.open(Pos::new(4, 4));
+        board.flag(Pos::new(0, 0));
+        let closed = board
+            .iter()
+            .filter(|(_, cell)| !matches!(cell.state, CellState::Opened))
+            .count();
+        assert_eq!(board.closed_count(), closed);
+    }
+
+    #[test]
+    fn place_mines_like_shuffle() {
+        use rand::seq::SliceRandom;
+
+        let safe = [0, 1, 2, 30, 31, 32, 60, 61, 62];
+        for (mine_count, seed) in [(0, 1), (10, 2), (99, 3), (441, 4), (500, 5)] {
+            let mut eligible: Vec<usize> = (0..450).filter(|idx| !safe.contains(idx)).collect();
+            let mut rng = StdRng::seed_from_u64(seed);
+            let shuffled = eligible.partial_shuffle(&mut rng, mine_count).0.to_vec();
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut picked = place_mines(450, &safe, mine_count, &mut rng);
+            // the shuffle fills the slice from its end
+            picked.reverse();
+            assert_eq!(picked, shuffled);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut board = Board::with_seed(9, 9, 10, 3).unwrap();
+        board.open(Pos::new(4, 4));
+        board.flag(Pos::new(0, 0));
+        let saved = serde_json::to_string(&board).unwrap();
+        let mut loaded: Board = serde_json::from_str(&saved).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), saved);
+        assert!(matches!(loaded.state(), BoardState::Playing));
+        assert_eq!(loaded.flag_count(), 1);
+        // the loaded mines are the saved ones
+        board.restart();
+        loaded.restart();
+        assert_eq!(
+            serde_json::to_string(&loaded).unwrap(),
+            serde_json::to_string(&board).unwrap()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_checked() {
+        let mut board = Board::with_seed(9, 9, 10, 3).unwrap();
+        board.open(Pos::new(4, 4));
+        let saved: serde_json::Value = serde_json::to_value(&board).unwrap();
+        let load = |change: fn(&mut serde_json::Value)| {
+            let mut saved = saved.clone();
+            change(&mut saved);
+            serde_json::from_value::<Board>(saved)
+        };
+        assert!(load(|_| ()).is_ok());
+        // ragged, empty and too small boards
+        assert!(load(|saved| saved["cells"][3].as_array_mut().unwrap().truncate(8)).is_err());
+        assert!(load(|saved| saved["cells"] = serde_json::json!([])).is_err());
+        assert!(load(|saved| saved["cells"].as_array_mut().unwrap().truncate(8)).is_err());
+        // counts that do not match the cells
+        assert!(load(|saved| saved["mine_count"] = 11.into()).is_err());
+        assert!(load(|saved| saved["flag_count"] = 1.into()).is_err());
+        assert!(load(|saved| saved["closed_cell_count"] = 0.into()).is_err());
+        assert!(load(|saved| saved["state"] = "Uninitialized".into()).is_err());
+    }
 }
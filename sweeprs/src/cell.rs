@@ -1,5 +1,6 @@
 /// Indicate what the mine contain
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum CellKind {
     Mine,
     Free,
@@ -8,6 +9,7 @@ pub enum CellKind {
 
 /// Hold the state of the cell
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum CellState {
     Closed,
     Flagged,
@@ -16,6 +18,7 @@ pub enum CellState {
 
 /// Default cell struct
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Cell {
     pub kind: CellKind,
     pub state: CellState,
@@ -23,6 +26,10 @@ pub struct Cell {
 
 /// Cell trait
 pub trait SweeperCell {
+    fn kind(&self) -> &CellKind;
+
+    fn state(&self) -> &CellState;
+
     fn open(&mut self) -> &CellKind;
 
     fn flag(&mut self) -> &CellState;
@@ -30,6 +37,14 @@ pub trait SweeperCell {
 
 /// Default implementation of the cell trait
 impl SweeperCell for Cell {
+    fn kind(&self) -> &CellKind {
+        &self.kind
+    }
+
+    fn state(&self) -> &CellState {
+        &self.state
+    }
+
     fn open(&mut self) -> &CellKind {
         if let CellState::Closed = self.state {
             self.state = CellState::Opened
@@ -0,0 +1,459 @@
+//! Minesweeper as a reinforcement learning environment, see `Env` and
+//! `VecEnv`.
+
+use std::{num::NonZeroUsize, thread};
+
+use crate::{
+    board::{Board, BoardResult, BoardState, SweeperBoard},
+    cell::{CellKind, CellState, SweeperCell},
+    error::Error,
+    pos::Pos,
+    rules::Move,
+};
+
+/// Planes of an observation, each `height * width` values row by row:
+/// closed cells first, then open cells with 0 to 8 adjacent mines, then
+/// the open mine that lost the game.
+pub const CHANNELS: usize = 11;
+const CLOSED_PLANE: usize = 0;
+const MINE_PLANE: usize = 10;
+/// Cells a thread of `VecEnv::step` gets at least, spawning it costs more
+/// than stepping fewer.
+const CELLS_PER_THREAD: usize = 1 << 16;
+
+/// Rewards handed out by `Env::step`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rewards {
+    /// The move cleared the board.
+    pub win: f32,
+    /// The move opened a mine.
+    pub loss: f32,
+    /// The move opened cells and the game goes on.
+    pub progress: f32,
+    /// The action was masked, nothing was played.
+    pub invalid: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Self {
+            win: 1.0,
+            loss: -1.0,
+            progress: 0.3,
+            invalid: -0.3,
+        }
+    }
+}
+
+/// What a step did besides its reward.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Info {
+    /// Cells opened by the move, more than one when it opened an empty
+    /// area.
+    pub opened: usize,
+    /// False when the action was masked.
+    pub valid: bool,
+    /// How the game ended, if it did.
+    pub result: Option<BoardResult>,
+}
+
+/// The `(observation, reward, done, info)` of a step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+    pub info: Info,
+}
+
+/// Minesweeper as a reinforcement learning environment, in the style of
+/// gym. An action opens the cell at index `row * width + column`, flags
+/// are left out since they never change the outcome. Observations are
+/// one-hot planes of the player view, see `CHANNELS`.
+pub struct Env<B: SweeperBoard = Board> {
+    board: B,
+    height: usize,
+    width: usize,
+    mine_count: usize,
+    rewards: Rewards,
+}
+
+impl<B: SweeperBoard> Env<B> {
+    /// Fails like `SweeperBoard::new` on an invalid configuration. The
+    /// environment starts as after `reset(0)`.
+    pub fn new(
+        height: usize,
+        width: usize,
+        mine_count: usize,
+        rewards: Rewards,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            board: B::with_seed(height, width, mine_count, 0)?,
+            height,
+            width,
+            mine_count,
+            rewards,
+        })
+    }
+
+    /// Start a new game, the same seed and actions always play the same
+    /// game. Returns the first observation.
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.board = B::with_seed(self.height, self.width, self.mine_count, seed)
+            .expect("the configuration is checked by Env::new");
+        self.observation()
+    }
+
+    /// Open the cell of `action`. Actions refused by `action_mask` are not
+    /// played and get the `invalid` reward, the game is done once it is
+    /// won or lost. Fails when `action` is not a cell of the board.
+    pub fn step(&mut self, action: usize) -> Result<Step, Error> {
+        if action >= self.action_count() {
+            return Err(Error::IndexOutOfBoundError);
+        }
+        Ok(self.play(Pos::new(action / self.width, action % self.width)))
+    }
+
+    fn play(&mut self, pos: Pos) -> Step {
+        let valid = self.playable(pos);
+        let (reward, opened) = if valid {
+            let closed = self.board.closed_count();
+            self.board.open(pos);
+            let reward = match self.board.state() {
+                BoardState::Finished(BoardResult::Win) => self.rewards.win,
+                BoardState::Finished(BoardResult::Lost) => self.rewards.loss,
+                _ => self.rewards.progress,
+            };
+            (reward, closed - self.board.closed_count())
+        } else {
+            (self.rewards.invalid, 0)
+        };
+        let result = match self.board.state() {
+            BoardState::Finished(result) => Some(*result),
+            _ => None,
+        };
+        Step {
+            observation: self.observation(),
+            reward,
+            done: result.is_some(),
+            info: Info {
+                opened,
+                valid,
+                result,
+            },
+        }
+    }
+
+    fn playable(&self, pos: Pos) -> bool {
+        !matches!(self.board.state(), BoardState::Finished(_))
+            && Move::Open.check(self.board.cell(pos).state()).is_ok()
+    }
+
+    /// `CHANNELS` planes of `height * width` values, one of which is 1 for
+    /// every cell. Mines are never shown, except the one that was opened.
+    pub fn observation(&self) -> Vec<f32> {
+        let cells = self.action_count();
+        let mut observation = vec![0.0; self.observation_len()];
+        for (pos, cell) in self.board.iter() {
+            let plane = match (cell.state(), cell.kind()) {
+                (CellState::Opened, CellKind::Mine) => MINE_PLANE,
+                (CellState::Opened, _) => 1 + self.board.count_adjacent_mines(pos),
+                _ => CLOSED_PLANE,
+            };
+            observation[plane * cells + pos.i * self.width + pos.j] = 1.0;
+        }
+        observation
+    }
+
+    /// Actions that would be played, one per cell: closed cells while the
+    /// game goes on.
+    pub fn action_mask(&self) -> Vec<bool> {
+        self.board
+            .iter()
+            .map(|(pos, _)| self.playable(pos))
+            .collect()
+    }
+
+    /// Number of actions, one per cell.
+    pub fn action_count(&self) -> usize {
+        self.height * self.width
+    }
+
+    pub fn observation_len(&self) -> usize {
+        CHANNELS * self.action_count()
+    }
+
+    pub fn rewards(&self) -> &Rewards {
+        &self.rewards
+    }
+
+    /// The board of the current game, to look at what the observation
+    /// hides.
+    pub fn board(&self) -> &B {
+        &self.board
+    }
+}
+
+/// Many environments of the same size stepped together, split over the
+/// available threads when there are enough cells to be worth it. A game
+/// that is done starts again right away, with the seed it had plus the
+/// number of environments, so the observation of its last step is the one
+/// of the new game. Its `info` tells how the previous one ended.
+pub struct VecEnv<B: SweeperBoard = Board> {
+    envs: Vec<Env<B>>,
+    seeds: Vec<u64>,
+}
+
+impl<B: SweeperBoard + Send> VecEnv<B> {
+    /// `count` environments as made by `Env::new`, started as after
+    /// `reset(0)`.
+    pub fn new(
+        count: usize,
+        height: usize,
+        width: usize,
+        mine_count: usize,
+        rewards: Rewards,
+    ) -> Result<Self, Error> {
+        let mut env = Self {
+            envs: (0..count)
+                .map(|_| Env::new(height, width, mine_count, rewards))
+                .collect::<Result<_, _>>()?,
+            seeds: vec![0; count],
+        };
+        env.reset(0);
+        Ok(env)
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn envs(&self) -> &[Env<B>] {
+        &self.envs
+    }
+
+    /// Reset environment `i` with `seed + i`, returning every first
+    /// observation.
+    pub fn reset(&mut self, seed: u64) -> Vec<Vec<f32>> {
+        self.seeds = (0..self.len() as u64)
+            .map(|i| seed.wrapping_add(i))
+            .collect();
+        self.envs
+            .iter_mut()
+            .zip(&self.seeds)
+            .map(|(env, &seed)| env.reset(seed))
+            .collect()
+    }
+
+    /// Step every environment with its action. Fails without playing
+    /// anything when an action is not a cell of the board.
+    ///
+    /// Panics when there is not one action per environment.
+    pub fn step(&mut self, actions: &[usize]) -> Result<Vec<Step>, Error> {
+        assert_eq!(actions.len(), self.len(), "one action per environment");
+        let Some(width) = self.envs.first().map(|env| env.width) else {
+            return Ok(Vec::new());
+        };
+        if actions
+            .iter()
+            .any(|&action| action >= self.envs[0].action_count())
+        {
+            return Err(Error::IndexOutOfBoundError);
+        }
+        let count = self.len() as u64;
+        let step = move |env: &mut Env<B>, seed: &mut u64, action: usize| {
+            let mut step = env.play(Pos::new(action / width, action % width));
+            if step.done {
+                *seed = seed.wrapping_add(count);
+                step.observation = env.reset(*seed);
+            }
+            step
+        };
+        let cells = self.len() * self.envs[0].action_count();
+        let threads = thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(cells / CELLS_PER_THREAD);
+        if threads <= 1 {
+            return Ok(self
+                .envs
+                .iter_mut()
+                .zip(&mut self.seeds)
+                .zip(actions)
+                .map(|((env, seed), &action)| step(env, seed, action))
+                .collect());
+        }
+        let chunk = self.len().div_ceil(threads);
+        Ok(thread::scope(|scope| {
+            let chunks: Vec<_> = self
+                .envs
+                .chunks_mut(chunk)
+                .zip(self.seeds.chunks_mut(chunk))
+                .zip(actions.chunks(chunk))
+                .map(|((envs, seeds), actions)| {
+                    scope.spawn(move || {
+                        envs.iter_mut()
+                            .zip(seeds)
+                            .zip(actions)
+                            .map(|((env, seed), &action)| step(env, seed, action))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            chunks
+                .into_iter()
+                .flat_map(|chunk| chunk.join().expect("a step panicked"))
+                .collect()
+        }))
+    }
+
+    pub fn action_masks(&self) -> Vec<Vec<bool>> {
+        self.envs.iter().map(Env::action_mask).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackedBoard;
+
+    fn env(seed: u64) -> Env {
+        let mut env = Env::new(9, 9, 10, Rewards::default()).unwrap();
+        env.reset(seed);
+        env
+    }
+
+    fn plane(observation: &[f32], plane: usize) -> &[f32] {
+        &observation[plane * 81..(plane + 1) * 81]
+    }
+
+    /// Actions opening every free cell, or the first mine, of a game
+    /// already started.
+    fn actions(env: &Env, mine: bool) -> Vec<usize> {
+        env.board()
+            .iter()
+            .filter(|(_, cell)| matches!(cell.kind(), CellKind::Mine) == mine)
+            .map(|(pos, _)| pos.i * 9 + pos.j)
+            .collect()
+    }
+
+    #[test]
+    fn step() {
+        let mut env = env(3);
+        let observation = env.observation();
+        assert_eq!(observation.len(), CHANNELS * 81);
+        assert!(plane(&observation, CLOSED_PLANE).iter().all(|&v| v == 1.0));
+        assert!(env.action_mask().iter().all(|&playable| playable));
+
+        let step = env.step(40).unwrap();
+        assert_eq!(step.reward, 0.3);
+        assert!(!step.done && step.info.valid && step.info.opened >= 9);
+        assert_eq!(plane(&step.observation, 1)[40], 1.0);
+        // every cell is in exactly one plane
+        for cell in 0..81 {
+            let planes = (0..CHANNELS).map(|p| plane(&step.observation, p)[cell]);
+            assert_eq!(planes.sum::<f32>(), 1.0);
+        }
+        assert!(!env.action_mask()[40]);
+
+        let step = env.step(40).unwrap();
+        assert_eq!(step.reward, -0.3);
+        assert_eq!(
+            step.info,
+            Info {
+                opened: 0,
+                valid: false,
+                result: None
+            }
+        );
+        assert!(matches!(env.step(81), Err(Error::IndexOutOfBoundError)));
+    }
+
+    #[test]
+    fn win_and_loss() {
+        let mut won = env(5);
+        won.step(40).unwrap();
+        let mut last = None;
+        for action in actions(&won, false) {
+            if won.action_mask()[action] {
+                last = Some(won.step(action).unwrap());
+            }
+        }
+        let last = last.unwrap();
+        assert!(last.done);
+        assert_eq!(last.reward, 1.0);
+        assert_eq!(last.info.result, Some(BoardResult::Win));
+        assert!(won.action_mask().iter().all(|&playable| !playable));
+        assert!(!won.step(0).unwrap().info.valid);
+
+        let mut lost = env(5);
+        lost.step(40).unwrap();
+        let mine = actions(&lost, true)[0];
+        let step = lost.step(mine).unwrap();
+        assert!(step.done);
+        assert_eq!(step.reward, -1.0);
+        assert_eq!(step.info.result, Some(BoardResult::Lost));
+        assert_eq!(plane(&step.observation, MINE_PLANE)[mine], 1.0);
+        assert_eq!(
+            plane(&step.observation, MINE_PLANE).iter().sum::<f32>(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn seeded() {
+        let play = |seed| {
+            let mut env = env(seed);
+            env.step(40).unwrap().observation
+        };
+        assert_eq!(play(7), play(7));
+        assert_ne!(play(7), play(8));
+        let mut packed: Env<PackedBoard> = Env::new(9, 9, 10, Rewards::default()).unwrap();
+        packed.reset(7);
+        assert_eq!(packed.step(40).unwrap().observation, play(7));
+        assert!(Env::<Board>::new(9, 9, 81, Rewards::default()).is_err());
+    }
+
+    #[test]
+    fn vectorised() {
+        let mut envs: VecEnv = VecEnv::new(5, 9, 9, 10, Rewards::default()).unwrap();
+        let observations = envs.reset(10);
+        assert_eq!(observations.len(), 5);
+        let steps = envs.step(&[40; 5]).unwrap();
+        for (i, step) in steps.iter().enumerate() {
+            let mut alone = env(10 + i as u64);
+            assert_eq!(step, &alone.step(40).unwrap());
+        }
+        assert!(envs.action_masks().iter().all(|mask| !mask[40]));
+        let masks = envs.action_masks();
+        let observations: Vec<_> = envs.envs().iter().map(Env::observation).collect();
+        assert!(envs.step(&[81, 0, 0, 0, 0]).is_err());
+        // nothing was played by the refused step
+        assert_eq!(envs.action_masks(), masks);
+        let after: Vec<_> = envs.envs().iter().map(Env::observation).collect();
+        assert_eq!(after, observations);
+
+        // env 0 loses and starts again with seed 10 + 5
+        let mine = actions(&envs.envs()[0], true)[0];
+        let steps = envs.step(&[mine, 40, 40, 40, 40]).unwrap();
+        assert!(steps[0].done);
+        assert_eq!(steps[0].info.result, Some(BoardResult::Lost));
+        assert_eq!(steps[0].observation, env(15).observation());
+        assert!(steps[1..].iter().all(|step| !step.info.valid));
+    }
+
+    #[test]
+    fn threaded() {
+        // enough cells to be split over threads, when there are any
+        let count = 2 * CELLS_PER_THREAD / 81 + 1;
+        let mut envs: VecEnv = VecEnv::new(count, 9, 9, 10, Rewards::default()).unwrap();
+        let steps = envs.step(&vec![40; count]).unwrap();
+        for i in [0, count / 2, count - 1] {
+            let mut alone = env(i as u64);
+            assert_eq!(steps[i], alone.step(40).unwrap());
+        }
+    }
+}
@@ -15,3 +15,5 @@ impl fmt::Display for Error {
         }
     }
 }
+
+impl std::error::Error for Error {}
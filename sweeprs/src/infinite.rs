@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use crate::{
+    board::{BoardResult, BoardState},
+    cell::{Cell, CellKind, CellState},
+    error::Error,
+    packed::{CLOSED, FLAGGED, MINE, OPENED, STATE_MASK},
+    reveal::{chord, reveal, Grid},
+};
+
+/// Width and height of a generated chunk.
+pub const CHUNK_SIZE: i64 = 16;
+
+/// Lowest accepted mine density. Below this, empty areas are likely to
+/// connect forever and a single click would never stop propagating.
+pub const MIN_DENSITY: f64 = 0.12;
+
+/// Highest accepted mine density.
+pub const MAX_DENSITY: f64 = 0.9;
+
+/// A square of cells packed the same way as `PackedBoard` cells, minus
+/// the cached count.
+struct Chunk {
+    cells: [u8; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+}
+
+/// Unbounded minesweeper board. Cells are addressed by signed coordinates
+/// and grouped in chunks that are only generated once the player touches
+/// them, so memory grows with the explored area. Mines are derived from
+/// the seed and the coordinate alone, the same seed always produces the
+/// same board. The game never ends with a win, the score is the number of
+/// free cells opened before hitting a mine.
+pub struct InfiniteBoard {
+    seed: u64,
+    density: f64,
+    threshold: u64,
+    chunks: HashMap<(i64, i64), Chunk>,
+    state: BoardState,
+    /// First opened cell, it and its neighbors never contain a mine.
+    origin: Option<(i64, i64)>,
+    opened_cell_count: usize,
+}
+
+/// SplitMix64 finalizer, good enough to turn coordinates into
+/// independent looking random numbers.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn split(i: i64, j: i64) -> ((i64, i64), usize) {
+    let key = (i.div_euclid(CHUNK_SIZE), j.div_euclid(CHUNK_SIZE));
+    let offset = i.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + j.rem_euclid(CHUNK_SIZE);
+    (key, offset as usize)
+}
+
+/// Helper methods to help implement the board
+impl InfiniteBoard {
+    /// Create a new infinite board where each cell has `density` chance of
+    /// being a mine. Return error if the density is outside of
+    /// `MIN_DENSITY..=MAX_DENSITY`.
+    pub fn new(density: f64, seed: u64) -> Result<Self, Error> {
+        if !(MIN_DENSITY..=MAX_DENSITY).contains(&density) {
+            return Err(Error::InvalidConfigError);
+        }
+        Ok(Self {
+            seed,
+            density,
+            threshold: (density * u64::MAX as f64) as u64,
+            chunks: HashMap::new(),
+            state: BoardState::Uninitialized,
+            origin: None,
+            opened_cell_count: 0,
+        })
+    }
+
+    /// Whether the cell would be a mine, regardless of its chunk being
+    /// generated or not.
+    fn generate_mine(&self, i: i64, j: i64) -> bool {
+        if let Some((i_origin, j_origin)) = self.origin {
+            if (i - i_origin).abs() <= 1 && (j - j_origin).abs() <= 1 {
+                return false;
+            }
+        }
+        mix(self.seed ^ mix(i as u64 ^ mix(j as u64))) < self.threshold
+    }
+
+    fn chunk_mut(&mut self, i: i64, j: i64) -> (&mut Chunk, usize) {
+        let (key, offset) = split(i, j);
+        if !self.chunks.contains_key(&key) {
+            let mut chunk = Chunk {
+                cells: [CLOSED; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+            };
+            for (offset, cell) in chunk.cells.iter_mut().enumerate() {
+                let offset = offset as i64;
+                let i = key.0 * CHUNK_SIZE + offset / CHUNK_SIZE;
+                let j = key.1 * CHUNK_SIZE + offset % CHUNK_SIZE;
+                if self.generate_mine(i, j) {
+                    *cell |= MINE;
+                }
+            }
+            self.chunks.insert(key, chunk);
+        }
+        (self.chunks.get_mut(&key).unwrap(), offset)
+    }
+
+    fn get(&self, i: i64, j: i64) -> u8 {
+        let (key, offset) = split(i, j);
+        match self.chunks.get(&key) {
+            Some(chunk) => chunk.cells[offset],
+            None if self.generate_mine(i, j) => CLOSED | MINE,
+            None => CLOSED,
+        }
+    }
+
+    /// The first opened cell and its neighbors are free cells. Chunks
+    /// generated before that, by flagging, are fixed up here.
+    fn initialize(&mut self, i: i64, j: i64) {
+        self.origin = Some((i, j));
+        for (i_nbr, j_nbr) in nbr_indices(i, j).chain([(i, j)]) {
+            let (chunk, offset) = self.chunk_mut(i_nbr, j_nbr);
+            chunk.cells[offset] &= !MINE;
+        }
+        self.state = BoardState::Playing;
+    }
+
+    /// Open a cell, with the same propagation and chording rules as
+    /// `Board::open`. Opening a mine finishes the game as lost, and
+    /// nothing is opened after that.
+    pub fn open(&mut self, i: i64, j: i64) -> &CellKind {
+        match self.state {
+            BoardState::Uninitialized => self.initialize(i, j),
+            BoardState::Playing => (),
+            // nothing opens after the loss, so the score stays
+            BoardState::Finished(_) => return self.kind_of(i, j),
+        }
+        match self.get(i, j) & STATE_MASK {
+            CLOSED => reveal(self, (i, j)),
+            OPENED => chord(self, (i, j)),
+            _ => (),
+        }
+        self.kind_of(i, j)
+    }
+
+    fn kind_of(&self, i: i64, j: i64) -> &'static CellKind {
+        if self.get(i, j) & MINE != 0 {
+            &CellKind::Mine
+        } else {
+            &CellKind::Free
+        }
+    }
+
+    /// Flag a cell, same as `Board::flag`.
+    pub fn flag(&mut self, i: i64, j: i64) -> &CellState {
+        let (chunk, offset) = self.chunk_mut(i, j);
+        let cell = &mut chunk.cells[offset];
+        match *cell & STATE_MASK {
+            CLOSED => {
+                *cell |= FLAGGED;
+                &CellState::Flagged
+            }
+            FLAGGED => {
+                *cell &= !FLAGGED;
+                &CellState::Closed
+            }
+            _ => &CellState::Opened,
+        }
+    }
+
+    pub fn state(&self) -> &BoardState {
+        &self.state
+    }
+
+    /// Current content of a cell. Untouched cells are closed, their kind
+    /// stays uninitialized until the first cell is opened.
+    pub fn cell(&self, i: i64, j: i64) -> Cell {
+        let cell = self.get(i, j);
+        let kind = match self.state {
+            BoardState::Uninitialized => CellKind::Uninitialized,
+            _ if cell & MINE != 0 => CellKind::Mine,
+            _ => CellKind::Free,
+        };
+        let state = match cell & STATE_MASK {
+            FLAGGED => CellState::Flagged,
+            OPENED => CellState::Opened,
+            _ => CellState::Closed,
+        };
+        Cell { kind, state }
+    }
+
+    pub fn count_adjacent_mines(&self, i: i64, j: i64) -> usize {
+        nbr_indices(i, j)
+            .filter(|&(i_nbr, j_nbr)| self.get(i_nbr, j_nbr) & MINE != 0)
+            .count()
+    }
+
+    /// Number of free cells opened so far.
+    pub fn score(&self) -> usize {
+        self.opened_cell_count
+    }
+
+    /// Number of chunks held in memory.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+}
+
+impl Grid for InfiniteBoard {
+    type Coord = (i64, i64);
+
+    fn is_closed(&self, (i, j): (i64, i64)) -> bool {
+        self.get(i, j) & STATE_MASK == CLOSED
+    }
+
+    fn is_flagged(&self, (i, j): (i64, i64)) -> bool {
+        self.get(i, j) & STATE_MASK == FLAGGED
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(self.state, BoardState::Playing)
+    }
+
+    fn mines_around(&self, (i, j): (i64, i64)) -> usize {
+        self.count_adjacent_mines(i, j)
+    }
+
+    fn nbrs(&self, (i, j): (i64, i64)) -> impl Iterator<Item = (i64, i64)> {
+        nbr_indices(i, j)
+    }
+
+    fn open_cell(&mut self, (i, j): (i64, i64)) {
+        let (chunk, offset) = self.chunk_mut(i, j);
+        chunk.cells[offset] |= OPENED;
+        if chunk.cells[offset] & MINE != 0 {
+            self.state = BoardState::Finished(BoardResult::Lost);
+        } else {
+            self.opened_cell_count += 1;
+        }
+    }
+}
+
+/// Neighboring coordinates from left to right, top to bottom.
+fn nbr_indices(i: i64, j: i64) -> impl Iterator<Item = (i64, i64)> {
+    (i - 1..=i + 1)
+        .flat_map(move |i_nbr| (j - 1..=j + 1).map(move |j_nbr| (i_nbr, j_nbr)))
+        .filter(move |&nbr| nbr != (i, j))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_board() {
+        assert!(InfiniteBoard::new(0.2, 0).is_ok());
+        assert!(InfiniteBoard::new(0.05, 0).is_err());
+        assert!(InfiniteBoard::new(0.95, 0).is_err());
+    }
+
+    #[test]
+    fn first_click_is_safe() {
+        for seed in 0..100 {
+            let mut board = InfiniteBoard::new(MAX_DENSITY, seed).unwrap();
+            board.flag(-1, -1);
+            assert!(matches!(board.open(0, 0), CellKind::Free));
+            assert_eq!(board.count_adjacent_mines(0, 0), 0);
+            assert!(matches!(board.state(), BoardState::Playing));
+        }
+    }
+
+    #[test]
+    fn same_seed_same_board() {
+        let mut first = InfiniteBoard::new(0.2, 42).unwrap();
+        let mut second = InfiniteBoard::new(0.2, 42).unwrap();
+        first.open(3, -7);
+        second.open(3, -7);
+        for i in -50..50 {
+            for j in -50..50 {
+                assert_eq!(
+                    matches!(first.cell(i, j).kind, CellKind::Mine),
+                    matches!(second.cell(i, j).kind, CellKind::Mine)
+                );
+            }
+        }
+        assert_eq!(first.score(), second.score());
+    }
+
+    #[test]
+    fn density() {
+        let mut board = InfiniteBoard::new(0.2, 7).unwrap();
+        board.open(0, 0);
+        let mines = (-200..200)
+            .flat_map(|i| (-200..200).map(move |j| (i, j)))
+            .filter(|&(i, j)| matches!(board.cell(i, j).kind, CellKind::Mine))
+            .count();
+        let ratio = mines as f64 / 160_000.0;
+        assert!((ratio - 0.2).abs() < 0.01, "ratio {}", ratio);
+    }
+
+    #[test]
+    fn memory_follows_exploration() {
+        let mut board = InfiniteBoard::new(0.2, 3).unwrap();
+        board.open(0, 0);
+        let explored = board.chunk_count();
+        // reading far away cells must not generate anything
+        board.cell(1_000_000, -1_000_000);
+        board.count_adjacent_mines(-1_000_000, 1_000_000);
+        assert_eq!(board.chunk_count(), explored);
+        board.flag(1_000_000, 1_000_000);
+        assert_eq!(board.chunk_count(), explored + 1);
+    }
+
+    #[test]
+    fn lose_on_mine() {
+        let mut board = InfiniteBoard::new(0.5, 11).unwrap();
+        board.open(0, 0);
+        let (i, j) = (2..)
+            .map(|j| (0, j))
+            .find(|&(i, j)| matches!(board.cell(i, j).kind, CellKind::Mine))
+            .unwrap();
+        let score = board.score();
+        assert!(matches!(board.open(i, j), CellKind::Mine));
+        assert!(matches!(
+            board.state(),
+            BoardState::Finished(BoardResult::Lost)
+        ));
+        assert_eq!(board.score(), score);
+    }
+
+    #[test]
+    fn nothing_opens_after_loss() {
+        let mut board = InfiniteBoard::new(0.5, 11).unwrap();
+        board.open(0, 0);
+        let (i, j) = (2..)
+            .map(|j| (0, j))
+            .find(|&(i, j)| matches!(board.cell(i, j).kind, CellKind::Mine))
+            .unwrap();
+        board.open(i, j);
+        let score = board.score();
+        let (i, j) = (2..)
+            .map(|j| (-5, j))
+            .find(|&(i, j)| {
+                matches!(board.cell(i, j).kind, CellKind::Free)
+                    && matches!(board.cell(i, j).state, CellState::Closed)
+            })
+            .unwrap();
+        assert!(matches!(board.open(i, j), CellKind::Free));
+        assert!(matches!(board.cell(i, j).state, CellState::Closed));
+        assert_eq!(board.score(), score);
+    }
+
+    #[test]
+    fn chord() {
+        let mut board = InfiniteBoard::new(0.3, 5).unwrap();
+        board.open(0, 0);
+        // find an opened number and flag every mine around it
+        let (i, j) = (-20..20)
+            .flat_map(|i| (-20..20).map(move |j| (i, j)))
+            .find(|&(i, j)| {
+                matches!(board.cell(i, j).state, CellState::Opened)
+                    && board.count_adjacent_mines(i, j) > 0
+                    && nbr_indices(i, j)
+                        .any(|(i, j)| matches!(board.cell(i, j).state, CellState::Closed))
+            })
+            .unwrap();
+        for (i_nbr, j_nbr) in nbr_indices(i, j) {
+            if let CellKind::Mine = board.cell(i_nbr, j_nbr).kind {
+                board.flag(i_nbr, j_nbr);
+            }
+        }
+        board.open(i, j);
+        assert!(matches!(board.state(), BoardState::Playing));
+        for (i_nbr, j_nbr) in nbr_indices(i, j) {
+            assert!(!matches!(board.cell(i_nbr, j_nbr).state, CellState::Closed));
+        }
+    }
+}
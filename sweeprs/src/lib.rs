@@ -3,22 +3,34 @@
 //! Example:
 //!
 //! ```
-//! use sweeprs::{Board, BoardState, BoardResult};
+//! use sweeprs::{Board, BoardState, BoardResult, Pos, SweeperBoard};
 //!
-//! let mut board = Board::new(9, 9, 10);
-//! board.open(4, 4);
-//! board.flag(0, 0);
+//! let mut board = Board::new(9, 9, 10).unwrap();
+//! board.open(Pos::new(4, 4));
+//! board.flag(Pos::new(0, 0));
 //! match board.state() {
 //!     BoardState::Playing => println!("Keep going!"),
-//!     BoardState::Finished(BoardResult::Win) => println!("You win!").
+//!     BoardState::Finished(BoardResult::Win) => println!("You win!"),
 //!     _ => (),
 //! }
 //! ```
 
 mod board;
 mod cell;
+pub mod env;
 mod error;
+mod infinite;
+mod packed;
+mod pos;
+mod reveal;
+mod rules;
+mod solver;
 
 pub use board::*;
 pub use cell::*;
 pub use error::*;
+pub use infinite::*;
+pub use packed::*;
+pub use pos::*;
+pub use rules::*;
+pub use solver::*;
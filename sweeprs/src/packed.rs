@@ -0,0 +1,419 @@
+use std::ops::Index;
+
+use crate::{
+    board::{mine_rng, place_mines, BoardResult, BoardState, SweeperBoard},
+    cell::{Cell, CellKind, CellState},
+    error::Error,
+    pos::Pos,
+    reveal::{chord, reveal, Grid},
+};
+
+pub(crate) const MINE: u8 = 0b0000_0001;
+pub(crate) const STATE_MASK: u8 = 0b0000_0110;
+pub(crate) const CLOSED: u8 = 0b0000_0000;
+pub(crate) const FLAGGED: u8 = 0b0000_0010;
+pub(crate) const OPENED: u8 = 0b0000_0100;
+const COUNT_SHIFT: u8 = 4;
+
+/// Every possible unpacked cell, so cells can be handed out by reference.
+static UNPACKED: [Cell; 9] = [
+    cell(CellKind::Uninitialized, CellState::Closed),
+    cell(CellKind::Uninitialized, CellState::Flagged),
+    cell(CellKind::Uninitialized, CellState::Opened),
+    cell(CellKind::Mine, CellState::Closed),
+    cell(CellKind::Mine, CellState::Flagged),
+    cell(CellKind::Mine, CellState::Opened),
+    cell(CellKind::Free, CellState::Closed),
+    cell(CellKind::Free, CellState::Flagged),
+    cell(CellKind::Free, CellState::Opened),
+];
+
+const fn cell(kind: CellKind, state: CellState) -> Cell {
+    Cell { kind, state }
+}
+
+/// Compact implementation of the SweeperBoard trait meant for very large
+/// boards. Every cell is packed into a single byte holding the mine bit,
+/// the cell state and the cached number of adjacent mines, stored in one
+/// flat row-major buffer.
+pub struct PackedBoard {
+    cells: Vec<u8>,
+    height: usize,
+    width: usize,
+    mine_count: usize,
+    state: BoardState,
+    closed_cell_count: usize,
+    flag_count: usize,
+    seed: Option<u64>,
+}
+
+/// Helper methods to help implement the trait
+impl PackedBoard {
+    /// Same as `Board::initialize`, the first opened cell and its
+    /// neighbors are guaranteed to be free cells. Adjacent mine counts
+    /// are computed once here and cached in the upper bits of each cell.
+    fn initialize(&mut self, i: usize, j: usize) {
+        let width = self.width;
+        let mut safe: Vec<usize> = self.nbr_indices(i, j).map(|(i, j)| i * width + j).collect();
+        safe.push(i * width + j);
+        safe.sort_unstable();
+        let mines = place_mines(
+            self.cells.len(),
+            &safe,
+            self.mine_count,
+            &mut *mine_rng(self.seed),
+        );
+        for idx in mines {
+            self.cells[idx] |= MINE;
+            for (i_nbr, j_nbr) in self.nbr_indices(idx / self.width, idx % self.width) {
+                self.cells[i_nbr * self.width + j_nbr] += 1 << COUNT_SHIFT;
+            }
+        }
+        self.state = BoardState::Playing;
+    }
+
+    /// Iterate over the indices of neighboring cells starting from left
+    /// to right, top to bottom, without allocating.
+    fn nbr_indices(&self, i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (height, width) = (self.height, self.width);
+        (i.saturating_sub(1)..(i + 2).min(height))
+            .flat_map(move |i_nbr| {
+                (j.saturating_sub(1)..(j + 2).min(width)).map(move |j_nbr| (i_nbr, j_nbr))
+            })
+            .filter(move |&nbr| nbr != (i, j))
+    }
+
+    fn cell_state(&self, i: usize, j: usize) -> u8 {
+        self.cells[i * self.width + j] & STATE_MASK
+    }
+
+    fn is_mine(&self, i: usize, j: usize) -> bool {
+        self.cells[i * self.width + j] & MINE != 0
+    }
+
+    fn unpack(&self, i: usize, j: usize) -> &'static Cell {
+        let kind = match self.state {
+            BoardState::Uninitialized => 0,
+            _ if self.is_mine(i, j) => 3,
+            _ => 6,
+        };
+        let state = match self.cell_state(i, j) {
+            FLAGGED => 1,
+            OPENED => 2,
+            _ => 0,
+        };
+        &UNPACKED[kind + state]
+    }
+}
+
+impl Grid for PackedBoard {
+    type Coord = Pos;
+
+    fn is_closed(&self, pos: Pos) -> bool {
+        self.cell_state(pos.i, pos.j) == CLOSED
+    }
+
+    fn is_flagged(&self, pos: Pos) -> bool {
+        self.cell_state(pos.i, pos.j) == FLAGGED
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(self.state, BoardState::Playing)
+    }
+
+    fn mines_around(&self, pos: Pos) -> usize {
+        self.count_adjacent_mines(pos)
+    }
+
+    fn nbrs(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        self.neighbours(pos)
+    }
+
+    fn open_cell(&mut self, pos: Pos) {
+        self.cells[pos.i * self.width + pos.j] |= OPENED;
+        self.closed_cell_count -= 1;
+        if self.is_mine(pos.i, pos.j) {
+            self.state = BoardState::Finished(BoardResult::Lost);
+        }
+    }
+}
+
+impl SweeperBoard for PackedBoard {
+    type Cell = Cell;
+
+    /// Create a new packed board, with the same constraints as `Board::new`.
+    fn new(height: usize, width: usize, mine_count: usize) -> Result<Self, Error> {
+        let cells = height
+            .checked_mul(width)
+            .filter(|&n| n <= u32::MAX as usize)
+            .ok_or(Error::InvalidConfigError)?;
+        if width < 9 || height < 9 || cells.checked_sub(9).is_none_or(|n| n < mine_count) {
+            return Err(Error::InvalidConfigError);
+        }
+        Ok(Self {
+            cells: vec![CLOSED; cells],
+            height,
+            width,
+            mine_count,
+            state: BoardState::Uninitialized,
+            closed_cell_count: cells,
+            flag_count: 0,
+            seed: None,
+        })
+    }
+
+    fn with_seed(height: usize, width: usize, mine_count: usize, seed: u64) -> Result<Self, Error> {
+        let mut board = Self::new(height, width, mine_count)?;
+        board.seed = Some(seed);
+        Ok(board)
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Read from the cache, no neighbor is visited.
+    fn count_adjacent_mines(&self, pos: Pos) -> usize {
+        assert!(pos.i < self.height && pos.j < self.width);
+        (self.cells[pos.i * self.width + pos.j] >> COUNT_SHIFT) as usize
+    }
+
+    fn mine_count(&self) -> usize {
+        self.mine_count
+    }
+
+    fn flag_count(&self) -> usize {
+        self.flag_count
+    }
+
+    fn closed_count(&self) -> usize {
+        self.closed_cell_count
+    }
+
+    fn cell(&self, pos: Pos) -> &Cell {
+        assert!(pos.i < self.height && pos.j < self.width);
+        self.unpack(pos.i, pos.j)
+    }
+
+    /// Same behavior as `Board::open`.
+    fn open(&mut self, pos: Pos) -> &CellKind {
+        let Pos { i, j } = pos;
+        if let BoardState::Uninitialized = self.state {
+            self.initialize(i, j);
+        }
+        match self.cell_state(i, j) {
+            CLOSED => reveal(self, pos),
+            OPENED => chord(self, pos),
+            _ => (),
+        }
+        if let BoardState::Playing = self.state {
+            if self.mine_count == self.closed_cell_count {
+                self.state = BoardState::Finished(BoardResult::Win);
+            }
+        }
+        if self.is_mine(i, j) {
+            &CellKind::Mine
+        } else {
+            &CellKind::Free
+        }
+    }
+
+    /// Same behavior as `Board::flag`.
+    fn flag(&mut self, pos: Pos) -> &CellState {
+        let cell = &mut self.cells[pos.i * self.width + pos.j];
+        match *cell & STATE_MASK {
+            CLOSED => {
+                *cell |= FLAGGED;
+                self.flag_count += 1;
+                &CellState::Flagged
+            }
+            FLAGGED => {
+                *cell &= !FLAGGED;
+                self.flag_count -= 1;
+                &CellState::Closed
+            }
+            _ => &CellState::Opened,
+        }
+    }
+
+    /// Same behavior as `Board::restart`, the cached mine counts stay.
+    fn restart(&mut self) {
+        if let BoardState::Uninitialized = self.state {
+            return;
+        }
+        for cell in &mut self.cells {
+            *cell &= !STATE_MASK;
+        }
+        self.flag_count = 0;
+        self.closed_cell_count = self.cells.len();
+        self.state = BoardState::Playing;
+    }
+
+    fn state(&self) -> &BoardState {
+        &self.state
+    }
+}
+
+impl Index<Pos> for PackedBoard {
+    type Output = Cell;
+
+    fn index(&self, pos: Pos) -> &Cell {
+        self.cell(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    #[test]
+    fn new_board() {
+        assert!(PackedBoard::new(9, 9, 10).is_ok());
+        assert!(PackedBoard::new(8, 9, 10).is_err());
+        assert!(PackedBoard::new(9, 7, 10).is_err());
+        assert!(PackedBoard::new(9, 9, 73).is_err());
+        assert!(PackedBoard::new(usize::MAX, 9, 0).is_err());
+        assert!(PackedBoard::new(1 << 16, 1 << 16, 0).is_err());
+    }
+
+    #[test]
+    fn init_board() {
+        let mut board = PackedBoard::new(16, 30, 99).unwrap();
+        board.open(Pos::new(7, 7));
+        let mut mine_count = 0;
+        for i in 0..board.height() {
+            for j in 0..board.width() {
+                if board.is_mine(i, j) {
+                    mine_count += 1;
+                }
+                let expected = board
+                    .nbr_indices(i, j)
+                    .filter(|&(i_nbr, j_nbr)| board.is_mine(i_nbr, j_nbr))
+                    .count();
+                assert_eq!(board.count_adjacent_mines(Pos::new(i, j)), expected);
+            }
+        }
+        assert_eq!(mine_count, 99);
+        for (i_nbr, j_nbr) in board.nbr_indices(7, 7) {
+            assert!(!board.is_mine(i_nbr, j_nbr));
+        }
+    }
+
+    #[test]
+    fn flag_cell() {
+        let mut board = PackedBoard::new(9, 9, 10).unwrap();
+        assert!(matches!(board.flag(Pos::new(0, 0)), CellState::Flagged));
+        assert!(matches!(board.open(Pos::new(0, 0)), CellKind::Free));
+        assert!(matches!(board[Pos::new(0, 0)].state, CellState::Flagged));
+        assert_eq!(board.flag_count(), 1);
+        assert_eq!(board.remaining_flags(), 9);
+        assert!(matches!(board.flag(Pos::new(0, 0)), CellState::Closed));
+        assert!(matches!(board[Pos::new(0, 0)].state, CellState::Closed));
+        assert_eq!(board.flag_count(), 0);
+    }
+
+    fn unpack_all(board: &PackedBoard) -> Vec<Vec<Cell>> {
+        (0..board.height())
+            .map(|i| {
+                (0..board.width())
+                    .map(|j| board[Pos::new(i, j)].clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frontier() {
+        let mut board = PackedBoard::new(9, 9, 10).unwrap();
+        board.open(Pos::new(4, 4));
+        let nested = Board::from_cells(unpack_all(&board), 10);
+        assert!(board.frontier().eq(nested.frontier()));
+    }
+
+    #[test]
+    fn out_of_bound() {
+        let mut board = PackedBoard::new(9, 9, 10).unwrap();
+        assert!(board.open_save(Pos::new(9, 0)).is_err());
+        assert!(board.open_save(Pos::new(0, 9)).is_err());
+        assert!(board.flag_save(Pos::new(9, 0)).is_err());
+        assert!(board.flag_save(Pos::new(0, 9)).is_err());
+    }
+
+    #[test]
+    fn huge_cascade() {
+        // a recursive flood fill would blow the stack here
+        let mut board = PackedBoard::new(1000, 1000, 0).unwrap();
+        board.open(Pos::new(500, 500));
+        assert!(matches!(
+            board.state(),
+            BoardState::Finished(BoardResult::Win)
+        ));
+    }
+
+    #[test]
+    fn restart() {
+        let mut board = PackedBoard::new(9, 9, 10).unwrap();
+        board.open(Pos::new(4, 4));
+        let before = unpack_all(&board);
+        board.flag(Pos::new(0, 0));
+        board.restart();
+        assert!(matches!(board.state(), BoardState::Playing));
+        assert_eq!(board.flag_count(), 0);
+        for (cell, old) in unpack_all(&board)
+            .iter()
+            .flatten()
+            .zip(before.iter().flatten())
+        {
+            assert!(matches!(cell.state, CellState::Closed));
+            assert_eq!(
+                std::mem::discriminant(&cell.kind),
+                std::mem::discriminant(&old.kind)
+            );
+        }
+        board.open(Pos::new(4, 4));
+        assert_eq!(board.count_adjacent_mines(Pos::new(4, 4)), 0);
+    }
+
+    #[test]
+    fn same_as_board() {
+        // replay the packed layout on a regular board and compare every move
+        let mut packed = PackedBoard::new(16, 16, 40).unwrap();
+        packed.open(Pos::new(8, 8));
+        let mut board = Board::from_cells(unpack_all(&packed), 40);
+        let moves = (0..16).flat_map(|i| (0..16).map(move |j| (i, j)));
+        for (i, j) in moves {
+            packed.open(Pos::new(i, j));
+            board.open(Pos::new(i, j));
+            for ((_, packed_cell), (_, cell)) in packed.iter().zip(board.iter()) {
+                assert_eq!(
+                    std::mem::discriminant(&packed_cell.state),
+                    std::mem::discriminant(&cell.state)
+                );
+            }
+            assert_eq!(
+                std::mem::discriminant(packed.state()),
+                std::mem::discriminant(board.state())
+            );
+        }
+    }
+
+    #[test]
+    fn seeded_like_board() {
+        let mut packed = PackedBoard::with_seed(16, 30, 99, 42).unwrap();
+        let mut board = Board::with_seed(16, 30, 99, 42).unwrap();
+        packed.open(Pos::new(3, 5));
+        board.open(Pos::new(3, 5));
+        assert_eq!(packed.closed_count(), board.closed_count());
+        for ((_, packed_cell), (_, cell)) in packed.iter().zip(board.iter()) {
+            assert_eq!(
+                std::mem::discriminant(&packed_cell.kind),
+                std::mem::discriminant(&cell.kind)
+            );
+        }
+    }
+}
@@ -0,0 +1,98 @@
+/// Coordinate of a cell on a board, `i` is the row and `j` the column,
+/// both starting from the top left corner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pos {
+    pub i: usize,
+    pub j: usize,
+}
+
+impl Pos {
+    pub fn new(i: usize, j: usize) -> Self {
+        Self { i, j }
+    }
+
+    /// Iterate over the neighbors of this position that fit in a board of
+    /// the given dimension, from left to right, top to bottom.
+    pub fn neighbours(self, height: usize, width: usize) -> Neighbours {
+        Neighbours {
+            center: self,
+            height,
+            width,
+            offset: 0,
+        }
+    }
+}
+
+impl From<(usize, usize)> for Pos {
+    fn from((i, j): (usize, usize)) -> Self {
+        Self { i, j }
+    }
+}
+
+impl From<Pos> for (usize, usize) {
+    fn from(pos: Pos) -> Self {
+        (pos.i, pos.j)
+    }
+}
+
+/// Iterator over the neighbors of a position, see `Pos::neighbours`.
+pub struct Neighbours {
+    center: Pos,
+    height: usize,
+    width: usize,
+    offset: usize,
+}
+
+impl Iterator for Neighbours {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        while self.offset < 9 {
+            let (i_offset, j_offset) = (self.offset / 3, self.offset % 3);
+            self.offset += 1;
+            // offsets are shifted by one to stay unsigned
+            let i_nbr = self.center.i + i_offset;
+            let j_nbr = self.center.j + j_offset;
+            if 0 < i_nbr
+                && i_nbr <= self.height
+                && 0 < j_nbr
+                && j_nbr <= self.width
+                && (i_offset, j_offset) != (1, 1)
+            {
+                return Some(Pos::new(i_nbr - 1, j_nbr - 1));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // corners and edges are covered through `Board::nbr_indices`
+
+    #[test]
+    fn centre() {
+        let nbrs: Vec<Pos> = Pos::new(4, 4).neighbours(9, 9).collect();
+        assert_eq!(nbrs.len(), 8);
+        assert!(nbrs
+            .iter()
+            .all(|nbr| nbr.i.abs_diff(4) <= 1 && nbr.j.abs_diff(4) <= 1));
+        assert!(!nbrs.contains(&Pos::new(4, 4)));
+    }
+
+    #[test]
+    fn not_square() {
+        let nbrs: Vec<(usize, usize)> = Pos::new(0, 15).neighbours(9, 16).map(Into::into).collect();
+        assert_eq!(nbrs, [(0, 14), (1, 14), (1, 15)]);
+        let nbrs: Vec<(usize, usize)> = Pos::new(8, 3).neighbours(9, 16).map(Into::into).collect();
+        assert_eq!(nbrs, [(7, 2), (7, 3), (7, 4), (8, 2), (8, 4)]);
+    }
+
+    #[test]
+    fn tuples() {
+        assert_eq!(Pos::from((2, 5)), Pos::new(2, 5));
+        assert_eq!(<(usize, usize)>::from(Pos::new(2, 5)), (2, 5));
+    }
+}
@@ -0,0 +1,54 @@
+/// What opening cells needs from a board, so `Board`, `PackedBoard` and
+/// `InfiniteBoard` share the propagation and chording rules whatever
+/// their storage and coordinates.
+pub(crate) trait Grid {
+    type Coord: Copy;
+
+    fn is_closed(&self, at: Self::Coord) -> bool;
+
+    fn is_flagged(&self, at: Self::Coord) -> bool;
+
+    fn is_playing(&self) -> bool;
+
+    fn mines_around(&self, at: Self::Coord) -> usize;
+
+    fn nbrs(&self, at: Self::Coord) -> impl Iterator<Item = Self::Coord>;
+
+    /// Open a closed cell, the game is lost if it is a mine.
+    fn open_cell(&mut self, at: Self::Coord);
+}
+
+/// Open a closed cell and propagate to its neighbors using an explicit
+/// stack, so huge empty areas cannot overflow the call stack. Cells are
+/// opened as they are pushed so none is visited twice, and propagation
+/// stops as soon as a mine is opened.
+pub(crate) fn reveal<G: Grid>(grid: &mut G, at: G::Coord) {
+    if !grid.is_closed(at) {
+        return;
+    }
+    grid.open_cell(at);
+    let mut stack = vec![at];
+    while let Some(at) = stack.pop() {
+        if !grid.is_playing() || grid.mines_around(at) > 0 {
+            continue;
+        }
+        let closed: Vec<_> = grid.nbrs(at).filter(|&nbr| grid.is_closed(nbr)).collect();
+        for nbr in closed {
+            grid.open_cell(nbr);
+            stack.push(nbr);
+        }
+    }
+}
+
+/// Reveal the closed neighbors of an opened number once as many of its
+/// neighbors are flagged as it has adjacent mines.
+pub(crate) fn chord<G: Grid>(grid: &mut G, at: G::Coord) {
+    let mine_count = grid.mines_around(at);
+    if mine_count == 0 || grid.nbrs(at).filter(|&nbr| grid.is_flagged(nbr)).count() != mine_count {
+        return;
+    }
+    let closed: Vec<_> = grid.nbrs(at).filter(|&nbr| grid.is_closed(nbr)).collect();
+    for nbr in closed {
+        reveal(grid, nbr);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+
+use crate::{
+    board::{BoardState, SweeperBoard},
+    cell::{CellKind, CellState, SweeperCell},
+    pos::Pos,
+};
+
+/// A move of the player, played by `SweeperBoard::play` only when the
+/// rules allow it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Move {
+    /// Open a closed cell.
+    Open,
+    /// Flag a closed cell or remove the flag of a flagged one.
+    Flag,
+    /// Open around an open number whose mines are all flagged.
+    Chord,
+}
+
+impl Move {
+    /// Refuse the move when the cell is not in a state it applies to.
+    pub fn check(self, cell: &CellState) -> Result<(), Refused> {
+        match (self, cell) {
+            (Self::Open, CellState::Closed) | (Self::Chord, CellState::Opened) => Ok(()),
+            (Self::Flag, CellState::Closed | CellState::Flagged) => Ok(()),
+            (Self::Open, CellState::Flagged) => Err(Refused::Flagged),
+            (Self::Open | Self::Flag, CellState::Opened) => Err(Refused::Opened),
+            (Self::Chord, _) => Err(Refused::NotOpened),
+        }
+    }
+}
+
+/// Why `SweeperBoard::play` did not play a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refused {
+    /// The cell is outside of the board.
+    OutOfBound,
+    /// The game is won or lost.
+    GameOver,
+    /// A flagged cell cannot be opened.
+    Flagged,
+    /// An open cell cannot be opened or flagged.
+    Opened,
+    /// Only an open cell can be chorded.
+    NotOpened,
+}
+
+impl fmt::Display for Refused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refused::OutOfBound => f.write_str("index is out of bound"),
+            Refused::GameOver => f.write_str("game is over"),
+            Refused::Flagged => f.write_str("cell is flagged"),
+            Refused::Opened => f.write_str("cell is already open"),
+            Refused::NotOpened => f.write_str("cell is not open"),
+        }
+    }
+}
+
+impl std::error::Error for Refused {}
+
+/// A cell as the player sees it, see `SweeperBoard::view`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellView {
+    /// An open cell and its number of adjacent mines.
+    Number(u8),
+    Closed,
+    Flagged,
+    /// An open mine, or any mine once the game is over.
+    Mine,
+}
+
+impl CellView {
+    pub const CLOSED: u8 = 9;
+    pub const FLAGGED: u8 = 10;
+    pub const MINE: u8 = 11;
+
+    /// One byte per cell, as the bindings hand them out: `0` to `8` for
+    /// open cells, then `CLOSED`, `FLAGGED` and `MINE`.
+    pub fn code(self) -> u8 {
+        match self {
+            CellView::Number(mine_count) => mine_count,
+            CellView::Closed => Self::CLOSED,
+            CellView::Flagged => Self::FLAGGED,
+            CellView::Mine => Self::MINE,
+        }
+    }
+
+    pub(crate) fn of<B: SweeperBoard>(board: &B, pos: Pos) -> Self {
+        let finished = matches!(board.state(), BoardState::Finished(_));
+        let cell = board.cell(pos);
+        match (cell.state(), cell.kind()) {
+            (CellState::Flagged, _) => CellView::Flagged,
+            (CellState::Opened, CellKind::Mine) => CellView::Mine,
+            (CellState::Closed, CellKind::Mine) if finished => CellView::Mine,
+            (CellState::Closed, _) => CellView::Closed,
+            (CellState::Opened, _) => CellView::Number(board.count_adjacent_mines(pos) as u8),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, BoardResult};
+
+    #[test]
+    fn moves() {
+        let mut board = Board::with_seed(9, 9, 10, 3).unwrap();
+        assert_eq!(
+            board.play(Move::Chord, Pos::new(4, 4)),
+            Err(Refused::NotOpened)
+        );
+        assert_eq!(
+            board.play(Move::Open, Pos::new(9, 0)),
+            Err(Refused::OutOfBound)
+        );
+        assert_eq!(board.play(Move::Open, Pos::new(4, 4)), Ok(()));
+        assert_eq!(board.play(Move::Open, Pos::new(4, 4)), Err(Refused::Opened));
+        assert_eq!(board.play(Move::Flag, Pos::new(4, 4)), Err(Refused::Opened));
+        assert_eq!(board.play(Move::Chord, Pos::new(4, 4)), Ok(()));
+
+        let closed = board.frontier().next().unwrap();
+        assert_eq!(board.play(Move::Flag, closed), Ok(()));
+        assert_eq!(board.play(Move::Open, closed), Err(Refused::Flagged));
+        assert_eq!(board.play(Move::Flag, closed), Ok(()));
+
+        let (mine, _) = board
+            .iter()
+            .find(|(_, cell)| matches!(cell.kind(), CellKind::Mine))
+            .unwrap();
+        assert_eq!(board.play(Move::Open, mine), Ok(()));
+        assert!(matches!(
+            board.state(),
+            BoardState::Finished(BoardResult::Lost)
+        ));
+        assert_eq!(board.play(Move::Flag, closed), Err(Refused::GameOver));
+    }
+
+    #[test]
+    fn view() {
+        let mut board = Board::with_seed(9, 9, 10, 3).unwrap();
+        assert!(board
+            .iter()
+            .all(|(pos, _)| board.view(pos) == CellView::Closed));
+        board.open(Pos::new(4, 4));
+        assert_eq!(board.view(Pos::new(4, 4)), CellView::Number(0));
+        let (mine, _) = board
+            .iter()
+            .find(|(_, cell)| matches!(cell.kind(), CellKind::Mine))
+            .unwrap();
+        board.flag(mine);
+        assert_eq!(board.view(mine).code(), CellView::FLAGGED);
+        let (other, _) = board
+            .iter()
+            .filter(|&(pos, _)| pos != mine)
+            .find(|(_, cell)| matches!(cell.kind(), CellKind::Mine))
+            .unwrap();
+        // mines stay hidden until the game is over
+        assert_eq!(board.view(other), CellView::Closed);
+        board.open(other);
+        assert_eq!(board.view(other).code(), CellView::MINE);
+        let mines = board
+            .iter()
+            .filter(|&(pos, _)| board.view(pos) == CellView::Mine)
+            .count();
+        assert_eq!(mines, 9);
+    }
+}
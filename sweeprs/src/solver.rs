@@ -0,0 +1,632 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{BoardState, CellKind, CellState, Pos, SweeperBoard, SweeperCell};
+
+/// Largest group of linked frontier cells solved by going through every
+/// mine layout, bigger groups get an estimate instead.
+const MAX_ENUMERATION: usize = 24;
+
+/// Why the hinted cell was picked.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reason {
+    /// Nothing is opened yet and the first click never hits a mine.
+    FirstMove,
+    /// `number` already touches as many mines as it shows, its other
+    /// closed neighbours are safe.
+    Satisfied { number: Pos },
+    /// Every closed neighbour of `inner` is also next to `outer` and both
+    /// show the same number of mines left, so the rest of the neighbours
+    /// of `outer` are safe.
+    Subset { inner: Pos, outer: Pos },
+    /// No mine in any layout matching the numbers and the mine count.
+    Enumeration,
+    /// Nothing is provably safe, the cell with the lowest chance of
+    /// hiding a mine.
+    Guess { probability: f64 },
+}
+
+/// A cell worth opening next.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub pos: Pos,
+    pub reason: Reason,
+}
+
+/// Closed cells around an opened number and the mines among them that are
+/// not known yet.
+#[derive(Clone, Debug)]
+struct Constraint {
+    number: Pos,
+    cells: Vec<Pos>,
+    mines: usize,
+}
+
+/// Find a safe cell to open, or the least risky one when there is none.
+///
+/// Only what the player can see is used: the numbers of opened cells, the
+/// size of the board and its mine count. Flags are ignored since they may
+/// be wrong, and so is the content of closed cells, but a flagged cell is
+/// only hinted when no other one will do. Returns `None` once the game is
+/// finished.
+pub fn hint<B: SweeperBoard>(board: &B) -> Option<Hint> {
+    match board.state() {
+        BoardState::Finished(_) => return None,
+        BoardState::Uninitialized => {
+            return Some(Hint {
+                pos: Pos::new(board.height() / 2, board.width() / 2),
+                reason: Reason::FirstMove,
+            })
+        }
+        BoardState::Playing => (),
+    }
+    let constraints = constraints(board);
+    let flagged: HashSet<Pos> = board
+        .iter()
+        .filter(|(_, cell)| matches!(cell.state(), CellState::Flagged))
+        .map(|(pos, _)| pos)
+        .collect();
+    let mut mines = HashSet::new();
+    loop {
+        let reduced = reduce(&constraints, &mines);
+        match deduce(&reduced, &flagged) {
+            Deduction::Safe(hint) => return Some(hint),
+            Deduction::Mines(found) => mines.extend(found),
+            Deduction::Nothing => return Some(guess(board, &reduced, &mines, &flagged)),
+        }
+    }
+}
+
+fn constraints<B: SweeperBoard>(board: &B) -> Vec<Constraint> {
+    board
+        .iter()
+        .filter(|(_, cell)| {
+            matches!(cell.state(), CellState::Opened) && matches!(cell.kind(), CellKind::Free)
+        })
+        .filter_map(|(pos, _)| {
+            let cells: Vec<Pos> = board
+                .neighbours(pos)
+                .filter(|&nbr| !matches!(board.cell(nbr).state(), CellState::Opened))
+                .collect();
+            if cells.is_empty() {
+                return None;
+            }
+            Some(Constraint {
+                number: pos,
+                mines: board.count_adjacent_mines(pos),
+                cells,
+            })
+        })
+        .collect()
+}
+
+/// Take the mines found so far out of the constraints.
+fn reduce(constraints: &[Constraint], mines: &HashSet<Pos>) -> Vec<Constraint> {
+    constraints
+        .iter()
+        .map(|constraint| {
+            let cells: Vec<Pos> = constraint
+                .cells
+                .iter()
+                .copied()
+                .filter(|pos| !mines.contains(pos))
+                .collect();
+            Constraint {
+                number: constraint.number,
+                mines: constraint.mines - (constraint.cells.len() - cells.len()),
+                cells,
+            }
+        })
+        .filter(|constraint| !constraint.cells.is_empty())
+        .collect()
+}
+
+enum Deduction {
+    Safe(Hint),
+    Mines(Vec<Pos>),
+    Nothing,
+}
+
+/// The first of the safe `cells` that is not flagged. When they all are,
+/// the first one goes to `fallback` instead, the player would have to
+/// remove the flag before opening it.
+fn pick(
+    cells: &[Pos],
+    reason: Reason,
+    flagged: &HashSet<Pos>,
+    fallback: &mut Option<Hint>,
+) -> Option<Hint> {
+    match cells.iter().find(|pos| !flagged.contains(pos)) {
+        Some(&pos) => Some(Hint { pos, reason }),
+        None => {
+            fallback.get_or_insert(Hint {
+                pos: cells[0],
+                reason,
+            });
+            None
+        }
+    }
+}
+
+/// Apply the single number rule, then the subset rule between numbers
+/// close enough to share cells. Stops at the first safe cell that is not
+/// flagged, a flagged one is only returned when nothing else is found.
+fn deduce(constraints: &[Constraint], flagged: &HashSet<Pos>) -> Deduction {
+    let mut mines = Vec::new();
+    let mut fallback = None;
+    for constraint in constraints {
+        if constraint.mines == 0 {
+            let reason = Reason::Satisfied {
+                number: constraint.number,
+            };
+            if let Some(hint) = pick(&constraint.cells, reason, flagged, &mut fallback) {
+                return Deduction::Safe(hint);
+            }
+            continue;
+        }
+        if constraint.mines == constraint.cells.len() {
+            mines.extend(&constraint.cells);
+        }
+    }
+    if !mines.is_empty() {
+        return Deduction::Mines(mines);
+    }
+    let by_number: HashMap<Pos, &Constraint> = constraints
+        .iter()
+        .map(|constraint| (constraint.number, constraint))
+        .collect();
+    for inner in constraints {
+        // numbers sharing a cell are at most two rows or columns apart
+        let (i, j) = (inner.number.i, inner.number.j);
+        for oi in i.saturating_sub(2)..=i + 2 {
+            for oj in j.saturating_sub(2)..=j + 2 {
+                let outer = match by_number.get(&Pos::new(oi, oj)) {
+                    Some(outer) if outer.number != inner.number => outer,
+                    _ => continue,
+                };
+                if !inner.cells.iter().all(|pos| outer.cells.contains(pos)) {
+                    continue;
+                }
+                let rest: Vec<Pos> = outer
+                    .cells
+                    .iter()
+                    .copied()
+                    .filter(|pos| !inner.cells.contains(pos))
+                    .collect();
+                if rest.is_empty() {
+                    continue;
+                }
+                let left = outer.mines - inner.mines;
+                if left == 0 {
+                    let reason = Reason::Subset {
+                        inner: inner.number,
+                        outer: outer.number,
+                    };
+                    if let Some(hint) = pick(&rest, reason, flagged, &mut fallback) {
+                        return Deduction::Safe(hint);
+                    }
+                    continue;
+                }
+                if left == rest.len() {
+                    mines.extend(rest);
+                }
+            }
+        }
+    }
+    match fallback {
+        _ if !mines.is_empty() => Deduction::Mines(mines),
+        Some(hint) => Deduction::Safe(hint),
+        None => Deduction::Nothing,
+    }
+}
+
+/// Mine layouts of one group of linked frontier cells, grouped by their
+/// number of mines.
+struct Layouts {
+    cells: Vec<Pos>,
+    /// Number of layouts with `k` mines at index `k`.
+    count: Vec<f64>,
+    /// Number of layouts with `k` mines where each cell is a mine.
+    mine_count: Vec<Vec<f64>>,
+}
+
+/// Chance of hiding a mine for every closed cell, from every layout
+/// matching the numbers, then pick the safest, preferring cells that are
+/// not flagged.
+fn guess<B: SweeperBoard>(
+    board: &B,
+    constraints: &[Constraint],
+    mines: &HashSet<Pos>,
+    flagged: &HashSet<Pos>,
+) -> Hint {
+    let frontier: HashSet<Pos> = constraints
+        .iter()
+        .flat_map(|constraint| constraint.cells.iter().copied())
+        .collect();
+    let interior: Vec<Pos> = board
+        .iter()
+        .filter(|(pos, cell)| {
+            !matches!(cell.state(), CellState::Opened)
+                && !frontier.contains(pos)
+                && !mines.contains(pos)
+        })
+        .map(|(pos, _)| pos)
+        .collect();
+    let mut left = board.mine_count() - mines.len();
+    let mut probabilities: Vec<(Pos, f64)> = Vec::new();
+    let mut groups = Vec::new();
+    let mut estimated = false;
+    for group in groups_of(constraints) {
+        if group.0.len() > MAX_ENUMERATION {
+            estimated = true;
+            // too many layouts, take the average density of the numbers and
+            // leave the expected mines out of the exact part
+            let mut expected = 0.0;
+            for &pos in &group.0 {
+                let densities: Vec<f64> = group
+                    .1
+                    .iter()
+                    .filter(|constraint| constraint.cells.contains(&pos))
+                    .map(|constraint| constraint.mines as f64 / constraint.cells.len() as f64)
+                    .collect();
+                let probability = densities.iter().sum::<f64>() / densities.len() as f64;
+                expected += probability;
+                probabilities.push((pos, probability));
+            }
+            left = left.saturating_sub(expected.round() as usize);
+        } else {
+            groups.push(enumerate(&group.0, &group.1));
+        }
+    }
+
+    // weight of every total number of mines in the exact groups, the rest
+    // of the mines go anywhere in the interior
+    let all = convolve(groups.iter().map(|layouts| &layouts.count));
+    let ln_weights: Vec<Option<f64>> = (0..all.len())
+        .map(|k| {
+            (k <= left && left - k <= interior.len()).then(|| ln_choose(interior.len(), left - k))
+        })
+        .collect();
+    let offset = ln_weights
+        .iter()
+        .flatten()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut weights: Vec<f64> = ln_weights
+        .iter()
+        .map(|ln| ln.map_or(0.0, |ln| (ln - offset).exp()))
+        .collect();
+    let mut total: f64 = all.iter().zip(&weights).map(|(count, w)| count * w).sum();
+    if total <= 0.0 {
+        // only when the estimate of a big group is off, count every
+        // layout the same
+        weights = vec![1.0; all.len()];
+        total = all.iter().sum();
+    }
+    for (index, layouts) in groups.iter().enumerate() {
+        let others = convolve(
+            groups
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != index)
+                .map(|(_, layouts)| &layouts.count),
+        );
+        for (cell, &pos) in layouts.cells.iter().enumerate() {
+            let mut mine = 0.0;
+            for (k, counts) in layouts.mine_count.iter().enumerate() {
+                for (rest, others) in others.iter().enumerate() {
+                    mine += counts[cell] * others * weights[k + rest];
+                }
+            }
+            probabilities.push((pos, mine / total));
+        }
+    }
+    if !interior.is_empty() {
+        let expected: f64 = all
+            .iter()
+            .enumerate()
+            .filter(|&(k, _)| k <= left)
+            .map(|(k, count)| count * weights[k] * (left - k) as f64)
+            .sum();
+        let pos = interior
+            .iter()
+            .find(|pos| !flagged.contains(pos))
+            .unwrap_or(&interior[0]);
+        probabilities.push((*pos, expected / total / interior.len() as f64));
+    }
+
+    let (pos, probability) = probabilities
+        .into_iter()
+        .min_by(|a, b| {
+            a.1.total_cmp(&b.1)
+                .then(flagged.contains(&a.0).cmp(&flagged.contains(&b.0)))
+        })
+        .expect("a board still being played has a closed free cell");
+    Hint {
+        pos,
+        // an estimate can be off enough to rule a mine out
+        reason: if probability <= f64::EPSILON && !estimated {
+            Reason::Enumeration
+        } else {
+            Reason::Guess { probability }
+        },
+    }
+}
+
+/// Split the frontier into groups of cells linked by the numbers around
+/// them, layouts of one group do not depend on the others.
+fn groups_of(constraints: &[Constraint]) -> Vec<(Vec<Pos>, Vec<Constraint>)> {
+    let mut groups: Vec<(Vec<Pos>, Vec<Constraint>)> = Vec::new();
+    for constraint in constraints {
+        let (linked, mut rest): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .partition(|group| group.0.iter().any(|pos| constraint.cells.contains(pos)));
+        let mut merged = (constraint.cells.clone(), vec![constraint.clone()]);
+        for group in linked {
+            for pos in group.0 {
+                if !merged.0.contains(&pos) {
+                    merged.0.push(pos);
+                }
+            }
+            merged.1.extend(group.1);
+        }
+        rest.push(merged);
+        groups = rest;
+    }
+    groups
+}
+
+/// Go through every mine layout of a group by backtracking, dropping a
+/// branch as soon as a number has too many or too few mines left.
+fn enumerate(cells: &[Pos], constraints: &[Constraint]) -> Layouts {
+    // constraints touching each cell, by index
+    let touching: Vec<Vec<usize>> = cells
+        .iter()
+        .map(|pos| {
+            constraints
+                .iter()
+                .enumerate()
+                .filter(|(_, constraint)| constraint.cells.contains(pos))
+                .map(|(index, _)| index)
+                .collect()
+        })
+        .collect();
+    let mut layouts = Layouts {
+        cells: cells.to_vec(),
+        count: vec![0.0; cells.len() + 1],
+        mine_count: vec![vec![0.0; cells.len()]; cells.len() + 1],
+    };
+    let mut state = Search {
+        touching,
+        placed: vec![0; constraints.len()],
+        open: constraints.iter().map(|c| c.cells.len()).collect(),
+        wanted: constraints.iter().map(|c| c.mines).collect(),
+        layout: vec![false; cells.len()],
+    };
+    state.search(0, &mut layouts);
+    layouts
+}
+
+struct Search {
+    touching: Vec<Vec<usize>>,
+    /// Mines placed and cells not decided yet around each number.
+    placed: Vec<usize>,
+    open: Vec<usize>,
+    wanted: Vec<usize>,
+    layout: Vec<bool>,
+}
+
+impl Search {
+    fn search(&mut self, cell: usize, layouts: &mut Layouts) {
+        if cell == self.layout.len() {
+            let k = self.layout.iter().filter(|&&mine| mine).count();
+            layouts.count[k] += 1.0;
+            for (index, &mine) in self.layout.iter().enumerate() {
+                if mine {
+                    layouts.mine_count[k][index] += 1.0;
+                }
+            }
+            return;
+        }
+        for mine in [false, true] {
+            let fits = self.touching[cell].iter().all(|&c| {
+                let placed = self.placed[c] + mine as usize;
+                // the cells still open after this one can hold the rest
+                placed <= self.wanted[c] && placed + self.open[c] > self.wanted[c]
+            });
+            if !fits {
+                continue;
+            }
+            for &c in &self.touching[cell] {
+                self.placed[c] += mine as usize;
+                self.open[c] -= 1;
+            }
+            self.layout[cell] = mine;
+            self.search(cell + 1, layouts);
+            for &c in &self.touching[cell] {
+                self.placed[c] -= mine as usize;
+                self.open[c] += 1;
+            }
+        }
+        self.layout[cell] = false;
+    }
+}
+
+/// Number of ways to pick a total of `k` mines across the groups.
+fn convolve<'a>(counts: impl Iterator<Item = &'a Vec<f64>>) -> Vec<f64> {
+    counts.fold(vec![1.0], |total, count| {
+        let mut next = vec![0.0; total.len() + count.len() - 1];
+        for (a, x) in total.iter().enumerate() {
+            for (b, y) in count.iter().enumerate() {
+                next[a + b] += x * y;
+            }
+        }
+        next
+    })
+}
+
+/// Natural log of the binomial coefficient, exact enough for weighting
+/// layouts on boards of any size.
+fn ln_choose(n: usize, k: usize) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+fn ln_factorial(n: usize) -> f64 {
+    if n < 32 {
+        (2..=n).map(|x| (x as f64).ln()).sum()
+    } else {
+        // Stirling series, well below f64 precision from 32 on
+        let n = n as f64;
+        n * n.ln() - n + 0.5 * (2.0 * std::f64::consts::PI * n).ln() + 1.0 / (12.0 * n)
+            - 1.0 / (360.0 * n * n * n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, Cell};
+
+    /// `*` is a closed mine, `.` a closed free cell and `o` an opened one.
+    fn from_layout(layout: &[&str]) -> Board {
+        let cells = layout
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| Cell {
+                        kind: if c == '*' {
+                            CellKind::Mine
+                        } else {
+                            CellKind::Free
+                        },
+                        state: if c == 'o' {
+                            CellState::Opened
+                        } else {
+                            CellState::Closed
+                        },
+                    })
+                    .collect()
+            })
+            .collect::<Vec<Vec<Cell>>>();
+        let mine_count = layout.concat().matches('*').count();
+        Board::from_cells(cells, mine_count)
+    }
+
+    #[test]
+    fn first_move() {
+        let board = Board::new(9, 9, 10).unwrap();
+        assert_eq!(
+            hint(&board),
+            Some(Hint {
+                pos: Pos::new(4, 4),
+                reason: Reason::FirstMove
+            })
+        );
+    }
+
+    #[test]
+    fn satisfied() {
+        let board = from_layout(&["*oo", "..."]);
+        assert_eq!(
+            hint(&board),
+            Some(Hint {
+                pos: Pos::new(1, 1),
+                reason: Reason::Satisfied {
+                    number: Pos::new(0, 2)
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn subset() {
+        let board = from_layout(&["ooo", "ooo", ".*."]);
+        assert_eq!(
+            hint(&board),
+            Some(Hint {
+                pos: Pos::new(2, 2),
+                reason: Reason::Subset {
+                    inner: Pos::new(1, 0),
+                    outer: Pos::new(1, 1)
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn flags_are_ignored() {
+        let mut board = from_layout(&["ooo", "ooo", ".*."]);
+        board.flag(Pos::new(2, 0));
+        board.flag(Pos::new(2, 2));
+        assert_eq!(hint(&board).unwrap().pos, Pos::new(2, 2));
+    }
+
+    #[test]
+    fn wrong_flag_on_safe_cell() {
+        let mut board = from_layout(&["*oo", "..."]);
+        board.flag(Pos::new(1, 1));
+        assert_eq!(
+            hint(&board),
+            Some(Hint {
+                pos: Pos::new(1, 2),
+                reason: Reason::Satisfied {
+                    number: Pos::new(0, 2)
+                }
+            })
+        );
+        let mut board = from_layout(&["ooo", "ooo", ".*."]);
+        board.flag(Pos::new(2, 2));
+        assert_eq!(
+            hint(&board),
+            Some(Hint {
+                pos: Pos::new(2, 0),
+                reason: Reason::Subset {
+                    inner: Pos::new(1, 2),
+                    outer: Pos::new(1, 1)
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn coin_flip() {
+        let board = from_layout(&["oo", "*."]);
+        let hint = hint(&board).unwrap();
+        match hint.reason {
+            Reason::Guess { probability } => assert!((probability - 0.5).abs() < 1e-9),
+            reason => panic!("expected a guess, got {:?}", reason),
+        }
+    }
+
+    #[test]
+    fn mine_count_decides() {
+        // the only mine is around the 1s, so the far cells are safe even
+        // though no number touches them
+        let board = from_layout(&["*o...", ".o..."]);
+        assert_eq!(
+            hint(&board),
+            Some(Hint {
+                pos: Pos::new(0, 3),
+                reason: Reason::Enumeration
+            })
+        );
+    }
+
+    #[test]
+    fn never_wrong_when_sure() {
+        for (height, width, mine_count, games) in [(9, 9, 10, 200), (16, 16, 40, 50)] {
+            for seed in 0..games {
+                let mut board = Board::with_seed(height, width, mine_count, seed).unwrap();
+                while let Some(hint) = hint(&board) {
+                    match hint.reason {
+                        Reason::Guess { probability } => {
+                            assert!((0.0..=1.0).contains(&probability))
+                        }
+                        Reason::FirstMove => (),
+                        _ => assert!(matches!(board.cell(hint.pos).kind(), CellKind::Free)),
+                    }
+                    board.open(hint.pos);
+                }
+            }
+        }
+    }
+}
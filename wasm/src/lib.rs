@@ -0,0 +1,268 @@
+//! WebAssembly bindings of the sweeprs engine, for the browser. Build with
+//! `wasm-pack build wasm` or `cargo build --target wasm32-unknown-unknown`
+//! and `wasm-bindgen`.
+//!
+//! ```js
+//! import init, { Board, GameState, CellSymbol } from "./pkg/sweeprs_wasm.js";
+//!
+//! await init();
+//! const board = new Board(9, 9, 10);
+//! board.open(4, 4);
+//! const view = board.view();
+//! if (view[0] === CellSymbol.Closed) board.flag(0, 0);
+//! if (board.state() === GameState.Won) console.log("You win!");
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use sweeprs::{BoardResult, BoardState, CellState, Move, Pos, Refused, SweeperBoard, SweeperCell};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameState {
+    /// No cell opened yet, the mines are not placed.
+    New,
+    Playing,
+    Won,
+    Lost,
+}
+
+/// Bytes of `Board.view` that are not numbers, those are `0` to `8` for
+/// open cells. The values are the ones of `sweeprs::CellView::code`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellSymbol {
+    Closed = 9,
+    Flagged = 10,
+    /// Only shown once the game is over.
+    Mine = 11,
+}
+
+/// A game of minesweeper, played from JavaScript.
+#[wasm_bindgen]
+pub struct Board {
+    board: sweeprs::Board,
+}
+
+#[wasm_bindgen]
+impl Board {
+    /// Throw when the board configuration is invalid. Without a seed one is
+    /// taken from `Math.random`, the same seed and first move always give
+    /// the same board.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        height: usize,
+        width: usize,
+        mines: usize,
+        seed: Option<u64>,
+    ) -> Result<Board, JsError> {
+        let board = match seed.or_else(random_seed) {
+            Some(seed) => sweeprs::Board::with_seed(height, width, mines, seed)?,
+            None => sweeprs::Board::new(height, width, mines)?,
+        };
+        Ok(Self { board })
+    }
+
+    pub fn height(&self) -> usize {
+        self.board.height()
+    }
+
+    pub fn width(&self) -> usize {
+        self.board.width()
+    }
+
+    pub fn mines(&self) -> usize {
+        self.board.mine_count()
+    }
+
+    /// Mines left to flag, negative when there are more flags than mines.
+    #[wasm_bindgen(js_name = minesLeft)]
+    pub fn mines_left(&self) -> isize {
+        self.board.remaining_flags()
+    }
+
+    pub fn state(&self) -> GameState {
+        match self.board.state() {
+            BoardState::Uninitialized => GameState::New,
+            BoardState::Playing => GameState::Playing,
+            BoardState::Finished(BoardResult::Win) => GameState::Won,
+            BoardState::Finished(BoardResult::Lost) => GameState::Lost,
+        }
+    }
+
+    /// Open a closed cell. Returns whether the move was played, it is not
+    /// on other cells or once the game is over. Throws when the cell is
+    /// outside of the board, like every move.
+    pub fn open(&mut self, row: usize, column: usize) -> Result<bool, JsError> {
+        self.play(Move::Open, row, column)
+    }
+
+    /// Flag a closed cell or remove the flag of a flagged one.
+    pub fn flag(&mut self, row: usize, column: usize) -> Result<bool, JsError> {
+        self.play(Move::Flag, row, column)
+    }
+
+    /// Open around an open number whose mines are all flagged. Returns
+    /// whether a cell was opened, nothing is around a `0` or a number
+    /// missing flags.
+    pub fn chord(&mut self, row: usize, column: usize) -> Result<bool, JsError> {
+        let pos = Pos::new(row, column);
+        let closed = |board: &sweeprs::Board| {
+            board
+                .neighbours(pos)
+                .filter(|&nbr| matches!(board.cell(nbr).state(), CellState::Closed))
+                .count()
+        };
+        let before = closed(&self.board);
+        Ok(self.play(Move::Chord, row, column)? && closed(&self.board) < before)
+    }
+
+    /// The board as its player sees it, one byte per cell row by row: `0`
+    /// to `8` for open cells and a `CellSymbol` for the others. A new array is
+    /// made on every call.
+    pub fn view(&self) -> Vec<u8> {
+        self.board
+            .iter()
+            .map(|(pos, _)| self.board.view(pos).code())
+            .collect()
+    }
+}
+
+impl Board {
+    /// A move refused by the rules is not played, only a cell outside of
+    /// the board throws.
+    fn play(&mut self, action: Move, row: usize, column: usize) -> Result<bool, JsError> {
+        match self.board.play(action, Pos::new(row, column)) {
+            Ok(()) => Ok(true),
+            Err(Refused::OutOfBound) => Err(sweeprs::Error::IndexOutOfBoundError.into()),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+/// The engine cannot ask the OS for a seed in the browser, JavaScript can.
+#[cfg(target_arch = "wasm32")]
+fn random_seed() -> Option<u64> {
+    let half = || (js_sys::Math::random() * 4_294_967_296.0) as u64;
+    Some(half() << 32 | half())
+}
+
+/// Natively the engine picks its own seed.
+#[cfg(not(target_arch = "wasm32"))]
+fn random_seed() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use sweeprs::{CellKind, CellView};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    const CLOSED: u8 = CellSymbol::Closed as u8;
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn symbols() {
+        assert_eq!(CellSymbol::Closed as u8, CellView::CLOSED);
+        assert_eq!(CellSymbol::Flagged as u8, CellView::FLAGGED);
+        assert_eq!(CellSymbol::Mine as u8, CellView::MINE);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn play() {
+        let mut board = Board::new(9, 9, 10, Some(3)).unwrap();
+        assert_eq!(board.state(), GameState::New);
+        assert_eq!(board.view(), vec![CLOSED; 81]);
+        assert!(board.open(4, 4).unwrap());
+        assert_eq!(board.state(), GameState::Playing);
+        let view = board.view();
+        assert_eq!(view[4 * 9 + 4], 0);
+        assert!(!board.open(4, 4).unwrap());
+        // there is nothing around a 0 to open
+        assert!(!board.chord(4, 4).unwrap());
+
+        let closed = view.iter().position(|&symbol| symbol == CLOSED).unwrap();
+        let (row, column) = (closed / 9, closed % 9);
+        assert!(!board.chord(row, column).unwrap());
+        assert!(board.flag(row, column).unwrap());
+        assert_eq!(board.mines_left(), 9);
+        assert!(!board.open(row, column).unwrap());
+        assert!(board.flag(row, column).unwrap());
+        assert_eq!(board.mines_left(), 10);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn chord() {
+        let mut board = Board::new(9, 9, 10, Some(3)).unwrap();
+        board.open(4, 4).unwrap();
+        // a number next to a closed cell that is not a mine
+        let (number, _) = board
+            .board
+            .iter()
+            .find(|&(pos, cell)| {
+                matches!(cell.state(), CellState::Opened)
+                    && board.board.count_adjacent_mines(pos) > 0
+                    && board.board.neighbours(pos).any(|nbr| {
+                        let cell = board.board.cell(nbr);
+                        matches!(cell.state(), CellState::Closed)
+                            && !matches!(cell.kind(), CellKind::Mine)
+                    })
+            })
+            .unwrap();
+        assert!(!board.chord(number.i, number.j).unwrap());
+        let mines: Vec<Pos> = board
+            .board
+            .neighbours(number)
+            .filter(|&nbr| matches!(board.board.cell(nbr).kind(), CellKind::Mine))
+            .collect();
+        for mine in mines {
+            board.flag(mine.i, mine.j).unwrap();
+        }
+        assert!(board.chord(number.i, number.j).unwrap());
+        assert!(!board.chord(number.i, number.j).unwrap());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn seeded() {
+        let view = |seed| {
+            let mut board = Board::new(16, 16, 40, Some(seed)).unwrap();
+            board.open(8, 8).unwrap();
+            board.view()
+        };
+        assert_eq!(view(7), view(7));
+        assert_ne!(view(7), view(8));
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn game_over() {
+        let mut board = Board::new(9, 9, 10, Some(5)).unwrap();
+        board.open(4, 4).unwrap();
+        // opening every closed cell in order hits a mine sooner or later
+        for (index, symbol) in board.view().into_iter().enumerate() {
+            if symbol == CLOSED {
+                board.open(index / 9, index % 9).unwrap();
+            }
+        }
+        assert_eq!(board.state(), GameState::Lost);
+        let view = board.view();
+        assert_eq!(
+            view.iter()
+                .filter(|&&symbol| symbol == CellSymbol::Mine as u8)
+                .count(),
+            10
+        );
+        let closed = view.iter().position(|&symbol| symbol == CLOSED).unwrap();
+        assert!(!board.flag(closed / 9, closed % 9).unwrap());
+    }
+
+    /// Errors are JavaScript values, only made in a wasm runner.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test]
+    fn errors() {
+        assert!(Board::new(9, 9, 81, None).is_err());
+        let mut board = Board::new(9, 9, 10, None).unwrap();
+        assert!(board.open(9, 0).is_err());
+        assert!(board.flag(0, 9).is_err());
+    }
+}